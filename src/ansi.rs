@@ -18,6 +18,7 @@ const SUCCESS: &str = "\x1b[32m";
 /// Error (magenta).
 const ERROR: &str = "\x1b[35m";
 
+#[derive(Clone, Copy)]
 pub struct Color {
     pub reset: &'static str,
     pub bold: &'static str,
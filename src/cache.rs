@@ -0,0 +1,323 @@
+//! A persistent cache of feature combinations that have already passed.
+//!
+//! Each package has its own folder within the cache directory. Each file within that folder is named
+//! after the hash of the package's sources and the hash of the check that was run, and contains one
+//! passing combination per line. When a package's sources change, the files for its old sources
+//! are deleted.
+
+use crate::manifest::Manifest;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The cached results of a single package, for a single version of its sources.
+pub struct PackageCache {
+    /// The combinations that have passed, as comma-separated lists of sorted features.
+    passed: HashSet<String>,
+    /// The file that new passing combinations are appended to.
+    file: File,
+}
+
+impl PackageCache {
+    /// Opens the cache for a package within `cache_dir`, creating it if it does not exist.
+    ///
+    /// `source_hash` should be calculated with [`hash_sources()`], and `check` should describe
+    /// everything about how combinations are checked, such as the commands that are run. Results
+    /// are only shared between runs with the same `source_hash` and `check`.
+    pub fn open(
+        cache_dir: &Path,
+        package: &str,
+        source_hash: u64,
+        check: &str,
+    ) -> io::Result<Self> {
+        let dir = cache_dir.join(package);
+        fs::create_dir_all(&dir)?;
+
+        let source_prefix = format!("{source_hash:016x}-");
+
+        // Delete results for old versions of the sources, since they will never be used again.
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+
+            if entry.file_type()?.is_file()
+                && !entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&source_prefix)
+            {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        let mut hasher = Fnv1a::new();
+        hasher.write(check.as_bytes());
+
+        let path = dir.join(format!("{source_prefix}{:016x}", hasher.finish()));
+
+        let passed = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().map(str::to_string).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e),
+        };
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self { passed, file })
+    }
+
+    /// Returns true if a combination of sorted features passed in a previous run.
+    pub fn contains(&self, features: &[&str]) -> bool {
+        self.passed.contains(&features.join(","))
+    }
+
+    /// Records that a combination of sorted features passed.
+    ///
+    /// This is written to disk immediately, so that it is not lost if the run is interrupted.
+    pub fn insert(&self, features: &[&str]) -> io::Result<()> {
+        writeln!(&self.file, "{}", features.join(","))
+    }
+}
+
+/// Returns the directories that can affect each package in a [`Manifest`], keyed by package name.
+///
+/// This includes the package's own directory and the directories of all of its path dependencies.
+/// Path dependencies of path dependencies are only included if they are within the workspace.
+pub fn source_dirs(manifest: &Manifest) -> HashMap<String, Vec<PathBuf>> {
+    fn dir_of(manifest_path: &Path) -> &Path {
+        manifest_path.parent().unwrap_or(Path::new("."))
+    }
+
+    let by_dir: HashMap<_, _> = manifest
+        .packages
+        .iter()
+        .map(|package| (dir_of(&package.manifest_path), package))
+        .collect();
+
+    manifest
+        .packages
+        .iter()
+        .map(|package| {
+            let mut dirs = BTreeSet::new();
+            dirs.insert(dir_of(&package.manifest_path).to_path_buf());
+
+            let mut stack = vec![package];
+
+            while let Some(package) = stack.pop() {
+                for path in package.dependencies.iter().filter_map(|d| d.path.as_ref()) {
+                    // Only visit each dependency once, in case of cycles with dev-dependencies.
+                    if dirs.insert(path.clone()) {
+                        stack.extend(by_dir.get(path.as_path()));
+                    }
+                }
+            }
+
+            (package.name.clone(), dirs.into_iter().collect())
+        })
+        .collect()
+}
+
+/// Hashes the contents of all files within `dirs`, along with the `Cargo.toml`, `Cargo.lock`, and
+/// `.cargo/config.toml` of `workspace_root` if they exist.
+///
+/// Hidden files and folders, folders named `target`, and the cache folder `cache_dir` are skipped.
+pub fn hash_sources(dirs: &[PathBuf], workspace_root: &Path, cache_dir: &Path) -> io::Result<u64> {
+    /// Hashes all files within `dir`, recursively, in a deterministic order.
+    fn hash_dir(root: &Path, dir: &Path, skip: &Path, hasher: &mut Fnv1a) -> io::Result<()> {
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_unstable_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if name.starts_with('.') || name == "target" {
+                continue;
+            }
+
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+
+            if path == skip {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                hash_dir(root, &path, skip, hasher)?;
+            } else if file_type.is_file() {
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+
+                hasher.write_prefixed(relative.to_string_lossy().as_bytes());
+                hasher.write_prefixed(&fs::read(&path)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Paths are compared in canonical form, since the cache folder is usually given relative to the
+    // current directory. If it does not exist yet, there is nothing within it to skip.
+    let skip = fs::canonicalize(cache_dir).unwrap_or_else(|_| cache_dir.to_path_buf());
+
+    let mut hasher = Fnv1a::new();
+
+    for dir in dirs {
+        let root = fs::canonicalize(dir)?;
+
+        hasher.write_prefixed(dir.to_string_lossy().as_bytes());
+        hash_dir(&root, &root, &skip, &mut hasher)?;
+    }
+
+    // The workspace manifest may set profiles, lints, and dependency versions for all members, and
+    // Cargo's config may set flags for them. `.cargo/config` is the legacy name of the latter.
+    for file in [
+        "Cargo.toml",
+        "Cargo.lock",
+        ".cargo/config.toml",
+        ".cargo/config",
+    ] {
+        match fs::read(workspace_root.join(file)) {
+            Ok(contents) => hasher.write_prefixed(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => hasher.write_prefixed(&[]),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(hasher.finish())
+}
+
+/// A 64-bit [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function)
+/// hasher.
+///
+/// Unlike [`DefaultHasher`](std::hash::DefaultHasher), its output will never change between Rust
+/// versions, so it is safe to write to disk.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Writes the length of `bytes` before `bytes` itself, so that `["ab", "c"]` and `["a", "bc"]`
+    /// hash differently.
+    fn write_prefixed(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a() {
+        // Test vectors from the FNV specification.
+        let mut empty = Fnv1a::new();
+        empty.write(b"");
+        assert_eq!(empty.finish(), 0xcbf29ce484222325);
+
+        let mut a = Fnv1a::new();
+        a.write(b"a");
+        assert_eq!(a.finish(), 0xaf63dc4c8601ec8c);
+
+        let mut foobar = Fnv1a::new();
+        foobar.write(b"foobar");
+        assert_eq!(foobar.finish(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn package_cache() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("flag-frenzy-cache-{}", std::process::id()));
+
+        {
+            let cache = PackageCache::open(&cache_dir, "foo", 1, "check").unwrap();
+            assert!(!cache.contains(&["bar", "baz"]));
+
+            cache.insert(&["bar", "baz"]).unwrap();
+            cache.insert(&[]).unwrap();
+        }
+
+        {
+            let cache = PackageCache::open(&cache_dir, "foo", 1, "check").unwrap();
+            assert!(cache.contains(&["bar", "baz"]));
+            assert!(cache.contains(&[]));
+            assert!(!cache.contains(&["bar"]));
+
+            // A different check does not share results.
+            let clippy = PackageCache::open(&cache_dir, "foo", 1, "clippy").unwrap();
+            assert!(!clippy.contains(&["bar", "baz"]));
+        }
+
+        {
+            // Changing the sources invalidates old results.
+            let cache = PackageCache::open(&cache_dir, "foo", 2, "check").unwrap();
+            assert!(!cache.contains(&["bar", "baz"]));
+            assert_eq!(fs::read_dir(cache_dir.join("foo")).unwrap().count(), 1);
+        }
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn hash_sources_skips_cache_dir() {
+        let root = std::env::temp_dir().join(format!("flag-frenzy-sources-{}", std::process::id()));
+        let member = root.join("member");
+        let cache_dir = root.join("cache");
+
+        fs::create_dir_all(member.join("src")).unwrap();
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(root.join("Cargo.toml"), "[workspace]").unwrap();
+        fs::write(member.join("src/lib.rs"), "").unwrap();
+
+        let dirs = [root.clone()];
+        let hash = hash_sources(&dirs, &root, &cache_dir).unwrap();
+
+        // Writing to the cache does not change the hash.
+        fs::write(cache_dir.join("results"), "foo").unwrap();
+        assert_eq!(hash_sources(&dirs, &root, &cache_dir).unwrap(), hash);
+
+        // Editing a source file does.
+        fs::write(member.join("src/lib.rs"), "fn foo() {}").unwrap();
+        assert_ne!(hash_sources(&dirs, &root, &cache_dir).unwrap(), hash);
+
+        // Members are affected by the workspace manifest, even though it is outside their folder.
+        let members = [member.clone()];
+        let hash = hash_sources(&members, &root, &cache_dir).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]",
+        )
+        .unwrap();
+        assert_ne!(hash_sources(&members, &root, &cache_dir).unwrap(), hash);
+
+        // So is Cargo's config, even though it is hidden.
+        let hash = hash_sources(&members, &root, &cache_dir).unwrap();
+        fs::create_dir_all(root.join(".cargo")).unwrap();
+        fs::write(
+            root.join(".cargo/config.toml"),
+            "[build]\nrustflags = [\"-D warnings\"]",
+        )
+        .unwrap();
+        assert_ne!(hash_sources(&members, &root, &cache_dir).unwrap(), hash);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Saving and loading the progress of a run, so that it can be resumed later.
+
+use crate::{runner::Platform, CheckFailure};
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// The progress of a run, which is saved after every checked combination.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Checkpoint {
+    /// The names of all packages that will be checked in this run, in order.
+    ///
+    /// This is used to detect when a checkpoint is resumed with different options, such as a
+    /// different `--chunk`.
+    pub packages: Vec<String>,
+    /// The names of all packages that have been completely checked.
+    pub completed_packages: Vec<String>,
+    /// The package that is currently being checked, if any.
+    pub current_package: Option<String>,
+    /// The number of checks of [`Self::current_package`] that have been completed, counting each
+    /// platform of a combination separately.
+    ///
+    /// Since checks are always generated in the same order, this is also the index of the next
+    /// check to run.
+    pub completed_combos: usize,
+    /// The features and platform of the last completed check of [`Self::current_package`].
+    ///
+    /// This is used to detect when a checkpoint is resumed with a config that generates different
+    /// checks, such as a lower `max_combo_size`.
+    #[serde(default)]
+    pub last_check: Option<(Vec<String>, Platform)>,
+    /// All failures found so far.
+    pub failures: Vec<CheckFailure>,
+    /// The seed used for `strategy = "random"` when none is configured.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl Checkpoint {
+    /// Loads a checkpoint from a JSON file.
+    ///
+    /// `packages` should be the names of the packages that will be checked in this run. If they do
+    /// not match the packages of the checkpoint, this will return an error.
+    pub fn load(path: &Path, packages: &[String]) -> anyhow::Result<Self> {
+        let contents =
+            fs::read(path).with_context(|| format!("Failed to read checkpoint {path:?}."))?;
+
+        let checkpoint: Self = serde_json::from_slice(&contents)
+            .with_context(|| format!("Failed to parse checkpoint {path:?}."))?;
+
+        ensure!(
+            checkpoint.packages == packages,
+            "Checkpoint {path:?} was created for a different set of packages. Make sure to pass the same options as the original run."
+        );
+
+        Ok(checkpoint)
+    }
+
+    /// Returns the number of combinations of a package that have already been checked.
+    pub fn progress_of(&self, package: &str) -> Option<usize> {
+        if self.completed_packages.iter().any(|p| p == package) {
+            None
+        } else if self.current_package.as_deref() == Some(package) {
+            Some(self.completed_combos)
+        } else {
+            Some(0)
+        }
+    }
+
+    /// Records that a package has been completely checked.
+    pub fn complete_package(&mut self, package: &str) {
+        self.completed_packages.push(package.to_string());
+        self.current_package = None;
+        self.completed_combos = 0;
+        self.last_check = None;
+    }
+
+    /// Records that a combination of a package has been checked.
+    pub fn complete_combo(&mut self, package: &str, features: &[&str], platform: &Platform) {
+        if self.current_package.as_deref() != Some(package) {
+            self.current_package = Some(package.to_string());
+            self.completed_combos = 0;
+        }
+
+        self.completed_combos += 1;
+        self.last_check = Some((
+            features.iter().map(|feature| feature.to_string()).collect(),
+            platform.clone(),
+        ));
+    }
+
+    /// Saves the checkpoint to a JSON file.
+    ///
+    /// The checkpoint is first written to a temporary file, then renamed, so that the existing
+    /// checkpoint is not corrupted if this is interrupted.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_vec(self).context("Failed to serialize checkpoint.")?;
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        fs::write(&temp_path, contents)
+            .with_context(|| format!("Failed to write checkpoint to {temp_path:?}."))?;
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to move checkpoint to {path:?}."))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let mut checkpoint = Checkpoint {
+            packages: vec!["bar".to_string(), "foo".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(checkpoint.progress_of("bar"), Some(0));
+
+        checkpoint.complete_combo("bar", &[], &Platform::default());
+        checkpoint.complete_combo("bar", &[], &Platform::default());
+
+        assert_eq!(checkpoint.progress_of("bar"), Some(2));
+        assert_eq!(checkpoint.progress_of("foo"), Some(0));
+
+        checkpoint.complete_package("bar");
+        checkpoint.complete_combo("foo", &["std"], &Platform::default());
+
+        assert_eq!(checkpoint.progress_of("bar"), None);
+        assert_eq!(checkpoint.progress_of("foo"), Some(1));
+        assert_eq!(
+            checkpoint.last_check,
+            Some((vec!["std".to_string()], Platform::default()))
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "flag-frenzy-checkpoint-{}.json",
+            std::process::id()
+        ));
+
+        let packages = vec!["foo".to_string()];
+
+        let mut checkpoint = Checkpoint {
+            packages: packages.clone(),
+            ..Default::default()
+        };
+
+        checkpoint.complete_combo("foo", &["std"], &Platform::default());
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path, &packages).unwrap();
+        assert_eq!(loaded.progress_of("foo"), Some(1));
+        assert_eq!(
+            loaded.last_check,
+            Some((vec!["std".to_string()], Platform::default()))
+        );
+
+        // Loading with different packages is an error.
+        assert!(Checkpoint::load(&path, &["bar".to_string()]).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
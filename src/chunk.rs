@@ -1,4 +1,4 @@
-use crate::{combos::estimate_combos, config::WorkspaceConfig, manifest::Package};
+use crate::{config::WorkspaceConfig, count_combos, manifest::Package};
 
 pub fn select_chunk(
     total_chunks: usize,
@@ -15,26 +15,26 @@ pub fn select_chunk(
     chunks.swap_remove(chunk)
 }
 
-/// Sorts a slice of [`Package`]s by the amount of feature combinations, based on
-/// [`estimate_combos()`].
+/// Sorts a slice of [`Package`]s by the amount of checks they require, as counted by
+/// [`count_combos()`].
 ///
-/// The returned [`Vec`] contains a tuples of the packages and their corresponding combinations. It
-/// is sorted so that the package with the greatest amount of combinations will be last.
-fn sort_by_combos(packages: Vec<Package>, config: &WorkspaceConfig) -> Vec<(Package, u128)> {
+/// This accounts for everything that changes how long a package takes to check, such as its
+/// strategy, rules, platforms, baselines, and leave-one-out combinations. Packages are chunked
+/// before the seed of the run is chosen, so that every chunk agrees on how random samples are
+/// counted.
+///
+/// The returned [`Vec`] contains a tuples of the packages and their corresponding checks. It is
+/// sorted so that the package with the greatest amount of checks will be last.
+fn sort_by_combos(packages: Vec<Package>, config: &WorkspaceConfig) -> Vec<(Package, usize)> {
     let mut sorted = Vec::with_capacity(packages.len());
 
-    // Calculate the amount of combos for each package, then add it to the list.
+    // Calculate the amount of checks for each package, then add it to the list.
     for package in packages {
-        let max_k = config
-            .get(&package.name)
-            .max_combo_size()
-            .map(|k| k as u128);
-
-        let combos = estimate_combos(package.features.len() as u128, max_k).unwrap();
+        let combos = count_combos(&package, config);
         sorted.push((package, combos));
     }
 
-    // Sort the list by the amount of combinations.
+    // Sort the list by the amount of checks.
     // TODO: Investigate whether this should be stable or unstable, since deteriminism is required.
     sorted.sort_unstable_by_key(|(_, combo)| *combo);
 
@@ -42,9 +42,9 @@ fn sort_by_combos(packages: Vec<Package>, config: &WorkspaceConfig) -> Vec<(Pack
 }
 
 /// Creates a list of chunks from a list of packages sorted by their max amount of combinations.
-fn create_chunks(mut sorted: Vec<(Package, u128)>, total_chunks: usize) -> Vec<Vec<Package>> {
+fn create_chunks(mut sorted: Vec<(Package, usize)>, total_chunks: usize) -> Vec<Vec<Package>> {
     let mut chunks = vec_from_fn(Vec::new, total_chunks);
-    let mut sizes = vec![0_u128; total_chunks];
+    let mut sizes = vec![0_usize; total_chunks];
 
     while let Some((package, combos)) = sorted.pop() {
         // Find the index of the chunk with the smallest size.
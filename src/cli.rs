@@ -1,14 +1,18 @@
 use anyhow::{ensure, Context};
-use argh::{FromArgValue, FromArgs};
+use argh::{EarlyExit, FromArgValue, FromArgs};
 use serde::Deserialize;
 use std::{
-    path::PathBuf,
+    env,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
 /// Automatically checks combinations of feature flags for a Cargo project.
 #[allow(clippy::upper_case_acronyms)]
 #[derive(FromArgs, Debug)]
+#[argh(
+    note = "Use `{command_name} diff <old> <new>` to compare two reports written by `--report-json`."
+)]
 pub struct CLI {
     /// the path to `Cargo.toml`, by default it is discovered through `cargo locate-project`
     #[argh(option, default = "locate_manifest_or_exit()")]
@@ -30,18 +34,94 @@ pub struct CLI {
     #[argh(option)]
     pub total_chunks: Option<usize>,
 
+    /// the amount of checks to run in parallel, defaults to 1
+    #[argh(option, short = 'j', default = "1")]
+    pub jobs: usize,
+
+    /// the timeout for checking a single combo in seconds, overrides the config
+    #[argh(option)]
+    pub timeout: Option<u64>,
+
+    /// the seed for `strategy = "random"`, overrides the config
+    #[argh(option)]
+    pub seed: Option<u64>,
+
+    /// the folder to cache passing combos in, so they are skipped if sources do not change
+    #[argh(option)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// the file to save progress to after every check, so the run can be resumed
+    #[argh(option)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// resume a run from a checkpoint file, skipping everything that was already checked
+    #[argh(option)]
+    pub resume: Option<PathBuf>,
+
+    /// stop checking after the first failure, same as `--max-failures 1`
+    #[argh(switch)]
+    pub fail_fast: bool,
+
+    /// stop checking after this many failures
+    #[argh(option)]
+    pub max_failures: Option<usize>,
+
+    /// move on to the next package after this many failures within a package
+    #[argh(option)]
+    pub max_package_failures: Option<usize>,
+
+    /// the maximum amount of errors shown for each failing combo, defaults to 3
+    #[argh(option, default = "3")]
+    pub max_diagnostics: usize,
+
+    /// list every failure in the failure report, instead of grouping similar failures together
+    #[argh(switch, short = 'v')]
+    pub verbose: bool,
+
     /// when to use color in the terminal output, either "always" or "never"
     #[argh(option, default = "ColorChoice::Always")]
     pub color: ColorChoice,
 
+    /// the file to write a JSON report of every check to
+    #[argh(option)]
+    pub report_json: Option<PathBuf>,
+
+    /// the file to write a JUnit XML report of every check to
+    #[argh(option)]
+    pub report_junit: Option<PathBuf>,
+
+    /// the file to write a SARIF report of compiler errors to
+    #[argh(option)]
+    pub report_sarif: Option<PathBuf>,
+
+    /// the file to write an HTML report with a feature matrix to
+    #[argh(option)]
+    pub report_html: Option<PathBuf>,
+
+    /// after a combo fails, search its subsets for the smallest ones that fail the same way
+    #[argh(switch)]
+    pub minimize: bool,
+
+    /// suggest `[[rule]]` blocks for the config that would skip the combos that failed
+    #[argh(switch)]
+    pub suggest_rules: bool,
+
+    /// show how far along the run is and how long it will take, with the duration of each check
+    #[argh(switch)]
+    pub progress: bool,
+
     /// print feature combos without running checks for them
     #[argh(switch)]
     pub dry_run: bool,
+
+    /// extra arguments passed to every Cargo command, specified after `--`
+    #[argh(positional)]
+    pub cargo_args: Vec<String>,
 }
 
 impl CLI {
     pub fn from_env() -> anyhow::Result<Self> {
-        let cli: Self = argh::from_env();
+        let mut cli: Self = argh::from_env();
 
         // Check that, if chunking is enabled, both flags are specified.
         ensure!(
@@ -54,6 +134,55 @@ impl CLI {
             ensure!(chunk < total_chunks, "Chunk must be within range [0..total_chunks), but is is {chunk} which is >= {total_chunks}.");
         }
 
+        // Check that there is at least one worker to run checks.
+        ensure!(cli.jobs > 0, "`--jobs` must be at least 1.");
+
+        // `--fail-fast` is a shortcut for `--max-failures 1`.
+        if cli.fail_fast {
+            ensure!(
+                cli.max_failures.is_none(),
+                "`--fail-fast` and `--max-failures` are incompatible with each other. Please pick one."
+            );
+
+            cli.max_failures = Some(1);
+        }
+
+        // Check that failure limits are not 0, which would stop before checking anything.
+        ensure!(
+            cli.max_failures != Some(0) && cli.max_package_failures != Some(0),
+            "`--max-failures` and `--max-package-failures` must be at least 1."
+        );
+
+        // Check that checkpoints are not used with dry runs, since nothing is checked.
+        ensure!(
+            !(cli.dry_run && (cli.checkpoint.is_some() || cli.resume.is_some())),
+            "`--checkpoint` and `--resume` cannot be used with `--dry-run`."
+        );
+
+        // Check that reports are not requested for dry runs, since nothing is checked.
+        ensure!(
+            !(cli.dry_run && cli.wants_report()),
+            "Reports cannot be written with `--dry-run`."
+        );
+
+        // Check that minimizing is not requested for dry runs, since nothing fails.
+        ensure!(
+            !(cli.dry_run && cli.minimize),
+            "`--minimize` cannot be used with `--dry-run`."
+        );
+
+        // Check that progress is not requested for dry runs, since nothing is checked.
+        ensure!(
+            !(cli.dry_run && cli.progress),
+            "`--progress` cannot be used with `--dry-run`."
+        );
+
+        // Check that rules are not suggested for dry runs, since nothing fails.
+        ensure!(
+            !(cli.dry_run && cli.suggest_rules),
+            "`--suggest-rules` cannot be used with `--dry-run`."
+        );
+
         // Check that chunking and specific package selection are not both enabled.
         ensure!(
             !(cli.chunk.is_some() && cli.package.is_some()),
@@ -62,6 +191,68 @@ impl CLI {
 
         Ok(cli)
     }
+
+    /// Returns true if any report of the run should be written.
+    pub fn wants_report(&self) -> bool {
+        self.report_json.is_some()
+            || self.report_junit.is_some()
+            || self.report_sarif.is_some()
+            || self.report_html.is_some()
+    }
+}
+
+/// Compares two reports written by `--report-json`, failing if any checks newly fail.
+#[derive(FromArgs, Debug)]
+pub struct DiffCLI {
+    /// the report of the earlier run
+    #[argh(positional)]
+    pub old: PathBuf,
+
+    /// the report of the later run
+    #[argh(positional)]
+    pub new: PathBuf,
+
+    /// when to use color in the terminal output, either "always" or "never"
+    #[argh(option, default = "ColorChoice::Always")]
+    pub color: ColorChoice,
+}
+
+impl DiffCLI {
+    /// Parses the arguments after `diff`, if it is the first argument.
+    ///
+    /// This is not an [`argh`] subcommand, since parsing [`CLI`] would locate the manifest even
+    /// though `diff` does not need it.
+    pub fn from_env() -> Option<Self> {
+        let args: Vec<String> = env::args().collect();
+
+        if args.get(1).map(String::as_str) != Some("diff") {
+            return None;
+        }
+
+        // Like `argh`, only show the file name of the executable in the usage.
+        let program = Path::new(&args[0]).file_name().map_or_else(
+            || args[0].clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+
+        let command = format!("{program} diff");
+        let rest: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+
+        // This mirrors what `argh::from_env()` does with errors and `--help`.
+        match Self::from_args(&[&command], &rest) {
+            Ok(cli) => Some(cli),
+            Err(EarlyExit { output, status }) => match status {
+                Ok(()) => {
+                    println!("{output}");
+                    std::process::exit(0);
+                }
+                Err(()) => {
+                    eprintln!("{output}\nRun {command} --help for more information.");
+                    std::process::exit(1);
+                }
+            },
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -0,0 +1,183 @@
+//! Grouping similar failures together, so that a single broken feature does not flood the failure
+//! report.
+
+use crate::{diagnostics::Diagnostic, runner::Outcome, CheckFailure};
+use std::collections::{BTreeSet, HashMap};
+
+/// A group of failures of the same package that failed in the same way.
+#[derive(Debug)]
+pub struct Cluster<'a> {
+    /// The failures in this cluster, in the order they were found.
+    pub failures: Vec<&'a CheckFailure>,
+    /// The features that every failure in this cluster contains, sorted.
+    pub common_features: Vec<&'a str>,
+}
+
+impl<'a> Cluster<'a> {
+    fn new(failures: Vec<&'a CheckFailure>) -> Self {
+        let common_features = common_features(&failures).into_iter().collect();
+
+        Self {
+            failures,
+            common_features,
+        }
+    }
+
+    /// The failure that every other failure in this cluster resembles.
+    pub fn first(&self) -> &'a CheckFailure {
+        self.failures[0]
+    }
+
+    /// The first error reported by every failure in this cluster, if any were captured.
+    pub fn primary_diagnostic(&self) -> Option<&'a Diagnostic> {
+        self.first().diagnostics.first()
+    }
+}
+
+/// Groups failures into clusters, largest first.
+///
+/// Failures are first grouped by their package, how they failed, and their primary diagnostic.
+/// Each group is then split by the feature that the most failures contain, until the failures of
+/// every cluster have at least one feature in common.
+pub fn cluster_failures<'a>(
+    failures: impl IntoIterator<Item = &'a CheckFailure>,
+) -> Vec<Cluster<'a>> {
+    // Group failures by their signature, in the order each signature was first found.
+    let mut groups: Vec<Vec<&CheckFailure>> = Vec::new();
+    let mut indices: HashMap<(&str, &Outcome, Option<&Diagnostic>), usize> = HashMap::new();
+
+    for failure in failures {
+        let signature = (
+            failure.package.as_str(),
+            &failure.outcome,
+            failure.diagnostics.first(),
+        );
+
+        let i = *indices.entry(signature).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+
+        groups[i].push(failure);
+    }
+
+    let mut clusters: Vec<Cluster> = groups.into_iter().flat_map(split_by_feature).collect();
+
+    // This sort is stable, so clusters of the same size stay in the order they were found.
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.failures.len()));
+
+    clusters
+}
+
+/// Splits a group of failures into clusters whose failures have at least one feature in common.
+fn split_by_feature(mut remaining: Vec<&CheckFailure>) -> Vec<Cluster<'_>> {
+    let mut clusters = Vec::new();
+
+    while !remaining.is_empty() {
+        let has_features = remaining.iter().any(|f| !f.features.is_empty());
+
+        if !has_features || !common_features(&remaining).is_empty() {
+            clusters.push(Cluster::new(remaining));
+            break;
+        }
+
+        // Find the feature contained in the most failures, preferring the first name on ties.
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        for failure in &remaining {
+            for feature in &failure.features {
+                *counts.entry(feature).or_default() += 1;
+            }
+        }
+
+        let (feature, _) = counts
+            .into_iter()
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+            .unwrap();
+
+        let (with, without) = remaining
+            .into_iter()
+            .partition(|f| f.features.iter().any(|f| f == feature));
+
+        clusters.push(Cluster::new(with));
+        remaining = without;
+    }
+
+    clusters
+}
+
+/// Returns the features that every failure contains.
+fn common_features<'a>(failures: &[&'a CheckFailure]) -> BTreeSet<&'a str> {
+    let Some((first, rest)) = failures.split_first() else {
+        return BTreeSet::new();
+    };
+
+    let mut common: BTreeSet<&str> = first.features.iter().map(String::as_str).collect();
+
+    for failure in rest {
+        common.retain(|&feature| failure.features.iter().any(|f| f == feature));
+    }
+
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Platform;
+
+    #[test]
+    fn cluster_by_feature_and_diagnostic() {
+        let missing = Diagnostic {
+            code: Some("E0433".to_string()),
+            message: "failed to resolve: use of undeclared crate or module `serde`".to_string(),
+            span: None,
+        };
+
+        let failure = |features: &[&str], diagnostics: &[&Diagnostic]| CheckFailure {
+            package: "foo".to_string(),
+            platform: Platform::default(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            outcome: Outcome::Failed {
+                command: "cargo-check".to_string(),
+            },
+            diagnostics: diagnostics.iter().map(|&d| d.clone()).collect(),
+            minimized: None,
+            baseline: None,
+        };
+
+        let failures = [
+            failure(&["a"], &[]),
+            failure(&["serialize"], &[&missing]),
+            failure(&["b"], &[]),
+            failure(&["a", "serialize"], &[&missing]),
+            failure(&["b", "serialize"], &[&missing]),
+            failure(&["a", "b"], &[]),
+            failure(&[], &[]),
+        ];
+
+        let clusters = cluster_failures(&failures);
+
+        let summary: Vec<(usize, Vec<&str>, bool)> = clusters
+            .iter()
+            .map(|c| {
+                (
+                    c.failures.len(),
+                    c.common_features.clone(),
+                    c.primary_diagnostic().is_some(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (3, vec!["serialize"], true),
+                // `a` and `b` are tied, so the first name is split off first.
+                (2, vec!["a"], false),
+                (1, vec!["b"], false),
+                (1, vec![], false),
+            ],
+        );
+    }
+}
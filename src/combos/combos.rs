@@ -88,7 +88,7 @@ impl Iterator for Combos {
             return Some((0..self.pool_size).collect());
         }
 
-        if self.output.len() == 0 {
+        if self.output.is_empty() {
             self.is_done = true;
             return Some(Box::new([]));
         }
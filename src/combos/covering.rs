@@ -0,0 +1,291 @@
+use super::Combos;
+
+/// `t` features, and which of them are enabled.
+struct Tuple {
+    /// The indices of the features, in ascending order.
+    features: Box<[usize]>,
+    /// Bit `i` is set if `features[i]` is enabled.
+    enabled: u32,
+}
+
+impl Tuple {
+    fn is_enabled(&self, i: usize) -> bool {
+        self.enabled & (1 << i) != 0
+    }
+
+    /// Returns true if a row enables and disables the same features as this tuple.
+    fn is_covered_by(&self, row: &[bool]) -> bool {
+        self.features
+            .iter()
+            .enumerate()
+            .all(|(i, &feature)| row[feature] == self.is_enabled(i))
+    }
+}
+
+/// Returns combinations of `n` features where, for every `t` features, each way of enabling and
+/// disabling them appears in at least one combination. Each combination is a list of the indices
+/// of the enabled features in ascending order.
+///
+/// Only combinations that pass `is_valid` are returned. If no valid combination could be found for
+/// some way of enabling `t` features, such as when a rule forbids them together, it is left out.
+///
+/// Combinations are built greedily, so there may be more than the smallest possible amount. They
+/// are returned from smallest to largest.
+pub fn covering_array(n: usize, t: usize, is_valid: impl Fn(&[usize]) -> bool) -> Vec<Vec<usize>> {
+    // If there are fewer than `t` features, every combination of them must be checked.
+    let t = t.min(n);
+
+    assert!(t < 32, "Cannot cover tuples of {t} features.");
+
+    let tuples: Vec<Tuple> = Combos::new(n, t)
+        .flat_map(|features| {
+            (0..1 << t).map(move |enabled| Tuple {
+                features: features.clone(),
+                enabled,
+            })
+        })
+        .collect();
+
+    // The tuples that contain each feature, so that choosing a feature only looks at those.
+    let mut by_feature = vec![Vec::new(); n];
+
+    for (i, tuple) in tuples.iter().enumerate() {
+        for &feature in tuple.features.iter() {
+            by_feature[feature].push(i);
+        }
+    }
+
+    let mut covered = vec![false; tuples.len()];
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+
+    // Count how many uncovered tuples a row would cover.
+    let coverage = |covered: &[bool], row: &[bool]| {
+        tuples
+            .iter()
+            .zip(covered)
+            .filter(|&(tuple, &covered)| !covered && tuple.is_covered_by(row))
+            .count()
+    };
+
+    // Tuples are covered in order, so every tuple before this one is already covered.
+    let mut seed = 0;
+
+    while seed < tuples.len() {
+        if covered[seed] {
+            seed += 1;
+            continue;
+        }
+
+        let seed_tuple = &tuples[seed];
+        let is_seed = |feature: usize| seed_tuple.features.contains(&feature);
+
+        // Start from the seed tuple, then enable or disable each other feature to cover as many
+        // other tuples as possible.
+        let mut assigned: Vec<Option<bool>> = vec![None; n];
+
+        for (i, &feature) in seed_tuple.features.iter().enumerate() {
+            assigned[feature] = Some(seed_tuple.is_enabled(i));
+        }
+
+        for feature in 0..n {
+            if assigned[feature].is_some() {
+                continue;
+            }
+
+            // Only tuples whose other features are already assigned can be covered so far.
+            let mut gains = [0; 2];
+
+            for &i in &by_feature[feature] {
+                let tuple = &tuples[i];
+
+                if covered[i] {
+                    continue;
+                }
+
+                let mut value = None;
+
+                let others_match = tuple.features.iter().enumerate().all(|(j, &other)| {
+                    if other == feature {
+                        value = Some(tuple.is_enabled(j));
+                        true
+                    } else {
+                        assigned[other] == Some(tuple.is_enabled(j))
+                    }
+                });
+
+                if let (true, Some(value)) = (others_match, value) {
+                    gains[usize::from(value)] += 1;
+                }
+            }
+
+            // Prefer disabling features on ties, which keeps combinations small.
+            assigned[feature] = Some(gains[1] > gains[0]);
+        }
+
+        let greedy: Vec<bool> = assigned.into_iter().map(Option::unwrap).collect();
+
+        let row = repair(
+            greedy,
+            &is_seed,
+            &|row: &[bool]| is_valid(&enabled(row)),
+            &|row| coverage(&covered, row),
+        );
+
+        match row {
+            Some(row) => {
+                for (i, tuple) in tuples.iter().enumerate() {
+                    if !covered[i] && tuple.is_covered_by(&row) {
+                        covered[i] = true;
+                    }
+                }
+
+                rows.push(enabled(&row));
+            }
+            // No valid combination covers the seed tuple.
+            None => covered[seed] = true,
+        }
+    }
+
+    rows.sort_unstable_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+    rows
+}
+
+/// Returns `row` if it is valid, or else the valid row found with the best coverage that keeps the
+/// features of the seed tuple as they are.
+///
+/// This first tries toggling a single feature of `row`. If that does not work, it tries only
+/// enabling the features of the seed tuple, along with at most one other feature.
+fn repair(
+    row: Vec<bool>,
+    is_seed: &impl Fn(usize) -> bool,
+    is_valid: &impl Fn(&[bool]) -> bool,
+    coverage: &impl Fn(&[bool]) -> usize,
+) -> Option<Vec<bool>> {
+    if is_valid(&row) {
+        return Some(row);
+    }
+
+    let best_of = |candidates: Vec<Vec<bool>>| {
+        candidates
+            .into_iter()
+            .filter(|candidate| is_valid(candidate))
+            .max_by_key(|candidate| coverage(candidate))
+    };
+
+    let toggled = (0..row.len())
+        .filter(|&feature| !is_seed(feature))
+        .map(|feature| {
+            let mut candidate = row.clone();
+            candidate[feature] = !candidate[feature];
+            candidate
+        })
+        .collect();
+
+    if let Some(row) = best_of(toggled) {
+        return Some(row);
+    }
+
+    let minimal: Vec<bool> = (0..row.len())
+        .map(|feature| is_seed(feature) && row[feature])
+        .collect();
+
+    if is_valid(&minimal) {
+        return Some(minimal);
+    }
+
+    let extended = (0..row.len())
+        .filter(|&feature| !minimal[feature] && !is_seed(feature))
+        .map(|feature| {
+            let mut candidate = minimal.clone();
+            candidate[feature] = true;
+            candidate
+        })
+        .collect();
+
+    best_of(extended)
+}
+
+/// Returns the indices of the enabled features in a row.
+fn enabled(row: &[bool]) -> Vec<usize> {
+    (0..row.len()).filter(|&feature| row[feature]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns true if every way of enabling `t` features appears in `rows`, except for those
+    /// rejected by `is_possible`.
+    fn covers_all(
+        n: usize,
+        t: usize,
+        rows: &[Vec<usize>],
+        is_possible: impl Fn(&Tuple) -> bool,
+    ) -> bool {
+        Combos::new(n, t).all(|features| {
+            (0..1 << t).all(|enabled| {
+                let tuple = Tuple {
+                    features: features.clone(),
+                    enabled,
+                };
+
+                !is_possible(&tuple)
+                    || rows.iter().any(|row| {
+                        let row: Vec<bool> = (0..n).map(|f| row.contains(&f)).collect();
+                        tuple.is_covered_by(&row)
+                    })
+            })
+        })
+    }
+
+    #[test]
+    fn pairwise_is_smaller_than_exhaustive() {
+        let rows = covering_array(10, 2, |_| true);
+
+        assert!(covers_all(10, 2, &rows, |_| true));
+        // Checking all combinations would take 1024 checks.
+        assert!(rows.len() <= 12, "{} combinations", rows.len());
+    }
+
+    #[test]
+    fn three_wise() {
+        let rows = covering_array(8, 3, |_| true);
+
+        assert!(covers_all(8, 3, &rows, |_| true));
+        assert!(rows.len() < 1 << 8);
+    }
+
+    #[test]
+    fn respects_validity() {
+        // Feature 0 and 1 cannot be enabled together, and 2 requires 3.
+        let is_valid = |row: &[usize]| {
+            !(row.contains(&0) && row.contains(&1)) && (!row.contains(&2) || row.contains(&3))
+        };
+
+        let rows = covering_array(6, 2, is_valid);
+
+        assert!(rows.iter().all(|row| is_valid(row)));
+
+        // Every pair is covered, except those that cannot be valid.
+        let is_possible = |tuple: &Tuple| {
+            let enabled = |feature| {
+                let i = tuple.features.iter().position(|&f| f == feature);
+                i.map(|i| tuple.is_enabled(i))
+            };
+
+            let both_0_and_1 = enabled(0) == Some(true) && enabled(1) == Some(true);
+            let two_without_three = enabled(2) == Some(true) && enabled(3) == Some(false);
+
+            !(both_0_and_1 || two_without_three)
+        };
+
+        assert!(covers_all(6, 2, &rows, is_possible));
+    }
+
+    #[test]
+    fn fewer_features_than_t() {
+        let rows = covering_array(2, 3, |_| true);
+        assert_eq!(rows, [vec![], vec![0], vec![1], vec![0, 1]]);
+    }
+}
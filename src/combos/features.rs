@@ -1,60 +1,411 @@
-use super::Combos;
+use super::{covering::covering_array, random::random_sample, Combos};
 use crate::{
-    config::{Config, Rule},
+    config::{schema::Base, ComboStrategy, Config, Rule},
     intern::{FeatureKey, FeatureStorage},
+    runner::Platform,
 };
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    ops::RangeInclusive,
+};
+
+/// Why a combination is not checked, either entirely or on a certain platform.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The combination does not pass a rule, or the rule does not allow the platform's toolchain.
+    Rule {
+        /// The index of the `[[rule]]` within the crate's config, starting at 0.
+        rule: usize,
+    },
+    /// The combination contains a feature that is already enabled by another feature within it,
+    /// so it is equivalent to a smaller combination.
+    Redundant {
+        /// The feature that is enabled twice.
+        feature: String,
+        /// The feature that enables it.
+        enabled_by: String,
+    },
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rule { rule } => write!(f, "skipped by rule {rule}"),
+            Self::Redundant {
+                feature,
+                enabled_by,
+            } => write!(f, "{feature} is already enabled by {enabled_by}"),
+        }
+    }
+}
+
+/// A combination that is checked before all others, if enabled in the config.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Baseline {
+    /// The features Cargo enables by default, which is just the `default` feature if there is one.
+    Default,
+    /// Every feature at once.
+    AllFeatures,
+}
+
+impl Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("default features"),
+            Self::AllFeatures => f.write_str("all features"),
+        }
+    }
+}
+
+/// A combination from [`all_combos()`], which may be skipped.
+pub struct PlatformCombo {
+    pub combo: Box<[FeatureKey]>,
+    /// The index of the platform this combination is checked on, or [`None`] if it is skipped on
+    /// all platforms.
+    pub platform: Option<usize>,
+    /// Why this combination is skipped, if it is.
+    pub skipped: Option<SkipReason>,
+}
+
+/// The features that combinations of a package are made from.
+pub struct ComboPool {
+    /// The features added to every combination, as configured by `base`.
+    pub base: Box<[FeatureKey]>,
+    /// The features that combinations choose from, sorted by name.
+    pub features: Box<[FeatureKey]>,
+    /// The combinations chosen by a covering array or random sample, as indices into
+    /// [`Self::features`], or [`None`] if every combination is generated.
+    ///
+    /// These are only built once per package, since they are expensive to choose.
+    pub rows: Option<Vec<Vec<usize>>>,
+}
+
+impl ComboPool {
+    pub fn new(storage: &FeatureStorage, config: Config<'_>) -> Self {
+        let all_keys = sorted_keys(storage);
+        let default = all_keys
+            .iter()
+            .copied()
+            .find(|&key| storage.get(key) == Some("default"));
+
+        let mut pool = match (config.base(), default) {
+            (Base::Default, Some(default)) => Self {
+                base: Box::new([default]),
+                // Features that are enabled by default would be redundant.
+                features: all_keys
+                    .iter()
+                    .copied()
+                    .filter(|&key| key != default && !storage.is_dependency(default, key))
+                    .collect(),
+                rows: None,
+            },
+            // Without a `default` feature, the default features are empty.
+            _ => Self {
+                base: Box::default(),
+                features: all_keys,
+                rows: None,
+            },
+        };
 
-pub fn feature_combos<'a>(
+        let sizes = combo_sizes(config, pool.features.len());
+        let rules = rules(storage, config);
+
+        pool.rows = match config.strategy() {
+            ComboStrategy::Exhaustive => None,
+            ComboStrategy::Covering { t } => Some(covering_rows(storage, &pool, &rules, sizes, t)),
+            ComboStrategy::Random { samples, seed } => Some(random_sample(
+                pool.features.len(),
+                sizes,
+                samples,
+                seed,
+                |indices| is_checked(storage, &rules, &pool.combo(indices)),
+            )),
+        };
+
+        pool
+    }
+
+    /// Returns the base features, followed by the features at `indices`.
+    fn combo(&self, indices: &[usize]) -> Box<[FeatureKey]> {
+        self.base
+            .iter()
+            .copied()
+            .chain(indices.iter().map(|&i| self.features[i]))
+            .collect()
+    }
+}
+
+/// Returns every combination of features between the min and max combo size, paired with the index
+/// of each platform it is checked on.
+///
+/// The [`baselines()`] come first and are never skipped, followed by the [`leave_one_out()`]
+/// combinations. Each combination is paired with all of its platforms before moving on to the next
+/// combination. Combinations that are skipped are yielded once with the reason, or once per
+/// platform if only some platforms are skipped.
+///
+/// `pool` must be built from the same `storage` and `config`.
+pub fn all_combos<'a>(
     storage: &'a FeatureStorage,
     config: Config<'_>,
-) -> impl Iterator<Item = Box<[FeatureKey]>> + 'a {
-    let total_features = storage.len();
-    let all_keys: Box<[_]> = storage.keys().collect();
+    pool: &'a ComboPool,
+    platforms: &'a [Platform],
+) -> impl Iterator<Item = PlatformCombo> + 'a {
+    let total_features = pool.features.len();
+    let sizes = combo_sizes(config, total_features);
+
+    let baselines = baselines(storage, config);
+    let left_out = leave_one_out(storage, config);
+    let rules = rules(storage, config);
+
+    let generated: Box<dyn Iterator<Item = Box<[FeatureKey]>>> = match pool.rows {
+        // Flatten all combinations of `(n: total_features, k: min_k..=max_k)`, converting arrays
+        // of `usize` indices to actual `FeatureKey`s.
+        None => Box::new(
+            sizes
+                .flat_map(move |k| Combos::new(total_features, k))
+                .map(|indices| pool.combo(&indices)),
+        ),
+        Some(ref rows) => Box::new(rows.iter().map(|indices| pool.combo(indices))),
+    };
+
+    // Baselines are checked even if rules would skip them, but only on the toolchains that the
+    // rules allow, since they would fail on others.
+    let baseline_combos: Vec<PlatformCombo> = baselines
+        .iter()
+        .flat_map(|(_, combo)| {
+            platforms.iter().enumerate().map(|(i, platform)| {
+                let toolchain = platform.toolchain.as_deref();
+
+                PlatformCombo {
+                    combo: combo.clone(),
+                    platform: Some(i),
+                    skipped: rules
+                        .iter()
+                        .position(|r| !r.allows_toolchain(combo, toolchain))
+                        .map(|rule| SkipReason::Rule { rule }),
+                }
+            })
+        })
+        .collect();
+
+    let combos = left_out
+        .clone()
+        .into_iter()
+        .chain(generated.filter(move |combo| !left_out.contains(combo)))
+        // Baselines were already checked.
+        .filter(move |combo| !baselines.iter().any(|(_, baseline)| baseline == combo))
+        .flat_map(move |combo| {
+            // Skip combinations that do not pass all rules for this crate.
+            let skipped = match rules.iter().position(|r| !r.validate(&combo)) {
+                Some(rule) => Some(SkipReason::Rule { rule }),
+                // Skip combinations that contain dependencies.
+                None => redundant_feature(storage, &combo),
+            };
+
+            if skipped.is_some() {
+                return vec![PlatformCombo {
+                    combo,
+                    platform: None,
+                    skipped,
+                }];
+            }
+
+            platforms
+                .iter()
+                .enumerate()
+                .map(|(i, platform)| {
+                    let toolchain = platform.toolchain.as_deref();
 
+                    PlatformCombo {
+                        combo: combo.clone(),
+                        platform: Some(i),
+                        skipped: rules
+                            .iter()
+                            .position(|r| !r.allows_toolchain(&combo, toolchain))
+                            .map(|rule| SkipReason::Rule { rule }),
+                    }
+                })
+                .collect()
+        });
+
+    baseline_combos.into_iter().chain(combos)
+}
+
+/// Returns the combinations that are checked before all others, as enabled by `check_default` and
+/// `check_all_features` in the config.
+///
+/// If both baselines have the same features, such as when the `default` feature enables every
+/// other feature, only [`Baseline::Default`] is returned.
+pub fn baselines(
+    storage: &FeatureStorage,
+    config: Config<'_>,
+) -> Vec<(Baseline, Box<[FeatureKey]>)> {
+    let all_keys = sorted_keys(storage);
+    let mut baselines = Vec::new();
+
+    if config.check_default() {
+        // Checks are run with `--no-default-features`, so enable the `default` feature explicitly.
+        let combo = all_keys
+            .iter()
+            .copied()
+            .filter(|&key| storage.get(key) == Some("default"))
+            .collect();
+
+        baselines.push((Baseline::Default, combo));
+    }
+
+    if config.check_all_features() && !baselines.iter().any(|(_, combo)| *combo == all_keys) {
+        baselines.push((Baseline::AllFeatures, all_keys));
+    }
+
+    baselines
+}
+
+/// Returns the default features with each of them removed in turn, if enabled by `leave_one_out` in
+/// the config.
+///
+/// Default features that another default feature enables are never removed, since they would stay
+/// enabled. Each combination only contains the remaining default features that no other remaining
+/// feature enables, so that it is not redundant.
+///
+/// Entries of `default` like `foo/bar` are kept and removed like any other feature, but `dep:foo`
+/// entries cannot be passed to Cargo, so they are never enabled. See
+/// [`FeatureStorage::default_dependencies()`].
+pub fn leave_one_out(storage: &FeatureStorage, config: Config<'_>) -> Vec<Box<[FeatureKey]>> {
+    if !config.leave_one_out() {
+        return Vec::new();
+    }
+
+    let mut defaults = storage.default_features().to_vec();
+    defaults.sort_unstable_by_key(|&key| storage.get(key));
+    defaults.dedup();
+
+    // Returns true if another feature in `features` enables `key`.
+    let is_enabled_by_other = |features: &[FeatureKey], key: FeatureKey| {
+        features
+            .iter()
+            .any(|&other| other != key && storage.is_dependency(other, key))
+    };
+
+    defaults
+        .iter()
+        .copied()
+        .filter(|&removed| !is_enabled_by_other(&defaults, removed))
+        .map(|removed| {
+            let remaining: Vec<FeatureKey> = defaults
+                .iter()
+                .copied()
+                .filter(|&key| key != removed)
+                .collect();
+
+            remaining
+                .iter()
+                .copied()
+                .filter(|&key| !is_enabled_by_other(&remaining, key))
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns every key in `storage`, sorted by their feature name.
+///
+/// `FeatureKey`s are random between runs, so this ensures combinations are always generated in the
+/// same order.
+fn sorted_keys(storage: &FeatureStorage) -> Box<[FeatureKey]> {
+    let mut all_keys: Box<[_]> = storage.keys().collect();
+    all_keys.sort_unstable_by_key(|&key| storage.get(key));
+    all_keys
+}
+
+/// Returns every combination that is checked, paired with the index of the platform it is checked
+/// on.
+///
+/// See [`all_combos()`] for the order they are returned in.
+pub fn platform_combos<'a>(
+    storage: &'a FeatureStorage,
+    config: Config<'_>,
+    pool: &'a ComboPool,
+    platforms: &'a [Platform],
+) -> impl Iterator<Item = (Box<[FeatureKey]>, usize)> + 'a {
+    all_combos(storage, config, pool, platforms).filter_map(|c| match c {
+        PlatformCombo {
+            combo,
+            platform: Some(platform),
+            skipped: None,
+        } => Some((combo, platform)),
+        _ => None,
+    })
+}
+
+/// Returns the range of combination sizes, from the min combo size up to the max combo size or the
+/// amount of features, whichever is smaller.
+fn combo_sizes(config: Config<'_>, total_features: usize) -> RangeInclusive<usize> {
     let max_k = config
         .max_combo_size()
         .unwrap_or(total_features)
         .min(total_features);
 
-    let rules: Box<[_]> = config
+    config.min_combo_size()..=max_k
+}
+
+/// Returns the rules of a package, with their features interned in `storage`.
+fn rules(storage: &FeatureStorage, config: Config<'_>) -> Box<[Rule]> {
+    config
         .rules()
         .iter()
         .cloned() // TODO: Do not clone this.
         .map(|r| Rule::from_schema(r, storage))
-        .collect();
+        .collect()
+}
 
-    (0..=max_k)
-        // Flatten all combinations of `(n: total_features, k: 0..=max_k)`.
-        .flat_map(move |k| Combos::new(total_features, k))
-        // Convert arrays of `usize` indices to actual `FeatureKey`s.
-        .map(move |feature_indices| {
-            let mut feature_keys = Vec::with_capacity(feature_indices.len());
+/// Returns a covering array of the features in the pool, as indices into [`ComboPool::features`].
+///
+/// Only combinations that would not be skipped, regardless of platform, are used.
+fn covering_rows(
+    storage: &FeatureStorage,
+    pool: &ComboPool,
+    rules: &[Rule],
+    sizes: RangeInclusive<usize>,
+    t: usize,
+) -> Vec<Vec<usize>> {
+    covering_array(pool.features.len(), t, |indices| {
+        sizes.contains(&indices.len()) && is_checked(storage, rules, &pool.combo(indices))
+    })
+}
 
-            for &i in feature_indices.iter() {
-                feature_keys.push(all_keys[i]);
-            }
+/// Returns true if a combination passes all rules and contains no redundant features.
+fn is_checked(storage: &FeatureStorage, rules: &[Rule], combo: &[FeatureKey]) -> bool {
+    rules.iter().all(|r| r.validate(combo)) && redundant_feature(storage, combo).is_none()
+}
 
-            feature_keys.into_boxed_slice()
-        })
-        // Only yield combinations that pass all rules for this crate.
-        .filter(move |combo| rules.iter().all(|r| r.validate(combo)))
-        // Only yield combinations that don't contain dependencies.
-        .filter(move |combo| {
-            combo.iter().all(|key| {
-                combo
-                    .iter()
-                    .all(|other_key| !storage.is_dependency(*key, *other_key))
+/// Returns [`SkipReason::Redundant`] if a combination contains a feature that another feature in
+/// it already enables.
+fn redundant_feature(storage: &FeatureStorage, combo: &[FeatureKey]) -> Option<SkipReason> {
+    combo.iter().find_map(|&key| {
+        combo
+            .iter()
+            .find(|&&other_key| storage.is_dependency(key, other_key))
+            .map(|&other_key| SkipReason::Redundant {
+                feature: storage.get(other_key).unwrap_or_default().to_string(),
+                enabled_by: storage.get(key).unwrap_or_default().to_string(),
             })
-        })
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
-    use crate::{config::WorkspaceConfig, intern::intern_features};
+    use crate::{
+        config::{schema, WorkspaceConfig},
+        intern::intern_features,
+        runner::Platform,
+    };
 
-    use super::feature_combos;
+    use super::{all_combos, leave_one_out, platform_combos, Baseline, ComboPool, SkipReason};
 
     #[test]
     fn test_filter_dependencies() {
@@ -66,22 +417,28 @@ mod tests {
 
         let schema_config = crate::config::schema::Config {
             max_combo_size: Some(3),
-            skip_optional_deps: None,
-            rules: vec![],
+            ..Default::default()
         };
         let workspace_config = WorkspaceConfig::new(HashMap::new(), schema_config);
         let storage = intern_features(features_map, workspace_config.get(""));
 
-        let mut combos: Vec<_> = feature_combos(&storage, workspace_config.get("foo"))
-            .map(|combo| {
-                let mut vec = combo
-                    .iter()
-                    .map(|key| storage.get(*key).unwrap())
-                    .collect::<Vec<_>>();
-                vec.sort();
-                vec
-            })
-            .collect();
+        let platforms = [Platform::default()];
+
+        let mut combos: Vec<_> = platform_combos(
+            &storage,
+            workspace_config.get("foo"),
+            &ComboPool::new(&storage, workspace_config.get("foo")),
+            &platforms,
+        )
+        .map(|(combo, _)| {
+            let mut vec = combo
+                .iter()
+                .map(|key| storage.get(*key).unwrap())
+                .collect::<Vec<_>>();
+            vec.sort();
+            vec
+        })
+        .collect();
         combos.sort();
 
         let mut expected = vec![
@@ -101,4 +458,304 @@ mod tests {
 
         assert_eq!(combos, expected);
     }
+
+    #[test]
+    fn skip_reasons() {
+        let mut features_map = HashMap::new();
+        features_map.insert("foo".to_string(), Vec::new());
+        features_map.insert("bar".to_string(), vec!["foo".to_string()]);
+        features_map.insert("simd".to_string(), Vec::new());
+
+        let crate_config = schema::Config {
+            max_combo_size: Some(2),
+            rules: vec![
+                // Forbid `foo` and `simd` together.
+                schema::Rule {
+                    when: schema::TrueOrFeatureSet::FeatureSet(schema::FeatureSet::One(
+                        "simd".to_string(),
+                    )),
+                    require: None,
+                    forbid: Some(schema::TrueOrFeatureSet::FeatureSet(
+                        schema::FeatureSet::One("foo".to_string()),
+                    )),
+                    toolchains: None,
+                },
+                // Only check `simd` on nightly.
+                schema::Rule {
+                    when: schema::TrueOrFeatureSet::FeatureSet(schema::FeatureSet::One(
+                        "simd".to_string(),
+                    )),
+                    require: None,
+                    forbid: None,
+                    toolchains: Some(vec!["nightly".to_string()]),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut crates = HashMap::new();
+        crates.insert("foo".to_string(), crate_config.into());
+
+        let workspace_config = WorkspaceConfig::new(crates, Default::default());
+        let config = workspace_config.get("foo");
+        let storage = intern_features(features_map, config);
+
+        let platforms = ["stable", "nightly"].map(|toolchain| Platform {
+            toolchain: Some(toolchain.to_string()),
+            ..Default::default()
+        });
+
+        let combos: Vec<_> = all_combos(
+            &storage,
+            config,
+            &ComboPool::new(&storage, config),
+            &platforms,
+        )
+        .map(|c| {
+            let mut features: Vec<_> = c
+                .combo
+                .iter()
+                .map(|&key| storage.get(key).unwrap())
+                .collect();
+            features.sort_unstable();
+
+            (features, c.platform, c.skipped)
+        })
+        .collect();
+
+        let rule = |rule| Some(SkipReason::Rule { rule });
+
+        assert_eq!(
+            combos,
+            [
+                (vec![], Some(0), None),
+                (vec![], Some(1), None),
+                (vec!["simd"], Some(0), rule(1)),
+                (vec!["simd"], Some(1), None),
+                (vec!["foo"], Some(0), None),
+                (vec!["foo"], Some(1), None),
+                (vec!["bar"], Some(0), None),
+                (vec!["bar"], Some(1), None),
+                (vec!["foo", "simd"], None, rule(0)),
+                (vec!["bar", "simd"], Some(0), rule(1)),
+                (vec!["bar", "simd"], Some(1), None),
+                (
+                    vec!["bar", "foo"],
+                    None,
+                    Some(SkipReason::Redundant {
+                        feature: "foo".to_string(),
+                        enabled_by: "bar".to_string(),
+                    }),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn baselines_and_min_combo_size() {
+        let mut features_map = HashMap::new();
+        features_map.insert("default".to_string(), vec!["foo".to_string()]);
+        features_map.insert("foo".to_string(), Vec::new());
+        features_map.insert("bar".to_string(), Vec::new());
+
+        let schema_config = schema::Config {
+            min_combo_size: Some(2),
+            max_combo_size: Some(2),
+            check_default: Some(true),
+            check_all_features: Some(true),
+            // Baselines are checked even if a rule would skip them.
+            rules: vec![schema::Rule {
+                when: schema::TrueOrFeatureSet::FeatureSet(schema::FeatureSet::One(
+                    "default".to_string(),
+                )),
+                require: None,
+                forbid: Some(schema::TrueOrFeatureSet::True),
+                toolchains: None,
+            }],
+            ..Default::default()
+        };
+
+        let mut crates = HashMap::new();
+        crates.insert("foo".to_string(), schema_config.into());
+
+        let workspace_config = WorkspaceConfig::new(crates, Default::default());
+        let config = workspace_config.get("foo");
+        let storage = intern_features(features_map, config);
+
+        let baselines: Vec<_> = super::baselines(&storage, config)
+            .into_iter()
+            .map(|(baseline, _)| baseline)
+            .collect();
+
+        assert_eq!(baselines, [Baseline::Default, Baseline::AllFeatures]);
+
+        let combos: Vec<_> = platform_combos(
+            &storage,
+            config,
+            &ComboPool::new(&storage, config),
+            &[Platform::default()],
+        )
+        .map(|(combo, _)| {
+            combo
+                .iter()
+                .map(|&key| storage.get(key).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+        // `["bar", "default"]` is skipped by the rule, and `["default", "foo"]` is redundant.
+        assert_eq!(
+            combos,
+            [
+                vec!["default"],
+                vec!["bar", "default", "foo"],
+                vec!["bar", "foo"],
+            ],
+        );
+    }
+
+    #[test]
+    fn baselines_follow_toolchains() {
+        let mut features_map = HashMap::new();
+        features_map.insert("simd".to_string(), Vec::new());
+
+        let crate_config = schema::Config {
+            check_all_features: Some(true),
+            // Only check `simd` on nightly.
+            rules: vec![schema::Rule {
+                when: schema::TrueOrFeatureSet::FeatureSet(schema::FeatureSet::One(
+                    "simd".to_string(),
+                )),
+                require: None,
+                forbid: None,
+                toolchains: Some(vec!["nightly".to_string()]),
+            }],
+            ..Default::default()
+        };
+
+        let mut crates = HashMap::new();
+        crates.insert("foo".to_string(), crate_config.into());
+
+        let workspace_config = WorkspaceConfig::new(crates, Default::default());
+        let config = workspace_config.get("foo");
+        let storage = intern_features(features_map, config);
+
+        let platforms = ["stable", "nightly"].map(|toolchain| Platform {
+            toolchain: Some(toolchain.to_string()),
+            ..Default::default()
+        });
+
+        let combos: Vec<_> = all_combos(
+            &storage,
+            config,
+            &ComboPool::new(&storage, config),
+            &platforms,
+        )
+        .map(|c| {
+            let features: Vec<_> = c
+                .combo
+                .iter()
+                .map(|&key| storage.get(key).unwrap())
+                .collect();
+
+            (features, c.platform, c.skipped)
+        })
+        .collect();
+
+        // The all features baseline is skipped on stable, like any other combination with `simd`.
+        assert_eq!(
+            combos,
+            [
+                (vec!["simd"], Some(0), Some(SkipReason::Rule { rule: 0 })),
+                (vec!["simd"], Some(1), None),
+                (vec![], Some(0), None),
+                (vec![], Some(1), None),
+            ],
+        );
+    }
+
+    #[test]
+    fn default_base_and_leave_one_out() {
+        let mut features_map = HashMap::new();
+        features_map.insert(
+            "default".to_string(),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+        features_map.insert("a".to_string(), Vec::new());
+        features_map.insert("b".to_string(), vec!["c".to_string()]);
+        features_map.insert("c".to_string(), Vec::new());
+        features_map.insert("x".to_string(), Vec::new());
+        features_map.insert("y".to_string(), Vec::new());
+
+        let schema_config = schema::Config {
+            base: Some(schema::Base::Default),
+            leave_one_out: Some(true),
+            ..Default::default()
+        };
+
+        let workspace_config = WorkspaceConfig::new(HashMap::new(), schema_config);
+        let config = workspace_config.get("foo");
+        let storage = intern_features(features_map, config);
+
+        let combos: Vec<_> = platform_combos(
+            &storage,
+            config,
+            &ComboPool::new(&storage, config),
+            &[Platform::default()],
+        )
+        .map(|(combo, _)| {
+            combo
+                .iter()
+                .map(|&key| storage.get(key).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+        // `c` is never left out, since `b` enables it. Combos are only made from features that are
+        // not enabled by default.
+        assert_eq!(
+            combos,
+            [
+                vec!["b"],
+                vec!["a", "c"],
+                vec!["default"],
+                vec!["default", "y"],
+                vec!["default", "x"],
+                vec!["default", "x", "y"],
+            ],
+        );
+    }
+
+    #[test]
+    fn leave_one_out_with_dependencies() {
+        let mut features_map = HashMap::new();
+        features_map.insert(
+            "default".to_string(),
+            vec!["a".to_string(), "dep:d".to_string(), "d/e".to_string()],
+        );
+        features_map.insert("a".to_string(), Vec::new());
+
+        let schema_config = schema::Config {
+            leave_one_out: Some(true),
+            ..Default::default()
+        };
+
+        let workspace_config = WorkspaceConfig::new(HashMap::new(), schema_config);
+        let config = workspace_config.get("foo");
+        let storage = intern_features(features_map, config);
+
+        let combos: Vec<_> = leave_one_out(&storage, config)
+            .iter()
+            .map(|combo| {
+                combo
+                    .iter()
+                    .map(|&key| storage.get(key).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // `d/e` also enables the dependency, but `dep:d` on its own cannot be passed to Cargo.
+        assert_eq!(combos, [vec!["d/e"], vec!["a"]]);
+        assert_eq!(storage.default_dependencies(), ["dep:d"]);
+    }
 }
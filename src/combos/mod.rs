@@ -1,11 +1,17 @@
 #[allow(clippy::module_inception)]
 mod combos;
+mod covering;
 mod features;
 mod ncr;
+mod random;
 
 pub use self::combos::Combos;
-pub use self::features::feature_combos;
+pub use self::features::{
+    all_combos, baselines, leave_one_out, platform_combos, Baseline, ComboPool, PlatformCombo,
+    SkipReason,
+};
 pub use self::ncr::estimate_combos;
+pub use self::random::pair_coverage;
 
 #[cfg(test)]
 pub use self::ncr::ncr;
@@ -31,11 +31,17 @@ pub fn ncr(n: u128, r: u128) -> Result<u128, IntegerOverflowError> {
     Ok(acc)
 }
 
-pub fn estimate_combos(n: u128, max_k: Option<u128>) -> Result<u128, IntegerOverflowError> {
+/// Calculates the number of combinations for a pool of size `n`, with a combo size from `min_k` to
+/// `max_k`, or to `n` if there is no max.
+pub fn estimate_combos(
+    n: u128,
+    min_k: u128,
+    max_k: Option<u128>,
+) -> Result<u128, IntegerOverflowError> {
     let mut sum = 0;
     let max_k = max_k.unwrap_or(n);
 
-    for k in 0..=max_k {
+    for k in min_k..=max_k {
         let c = ncr(n, k)?;
         sum = c.saturating_add(sum);
     }
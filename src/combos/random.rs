@@ -0,0 +1,134 @@
+use crate::intern::FeatureKey;
+use std::{collections::BTreeSet, ops::RangeInclusive};
+
+/// How many random combinations in a row may be invalid or repeated before sampling gives up,
+/// which happens when there are fewer valid combinations than requested.
+const MAX_REJECTIONS: usize = 10_000;
+
+/// A small, fast pseudo-random number generator.
+///
+/// This is [SplitMix64](https://prng.di.unimi.it/splitmix64.c), which is not suitable for
+/// cryptography, but always produces the same numbers for the same seed on every platform.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the range `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        // The modulo bias is negligible for the small ranges used here.
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Returns up to `samples` random, unique combinations of `n` features, each with an amount of
+/// features within `sizes`. Each combination is a list of feature indices in ascending order.
+///
+/// The size of each combination is chosen uniformly, so small combinations are as likely as large
+/// ones. Only combinations that pass `is_valid` are returned. The same seed always returns the same
+/// combinations, from smallest to largest.
+pub fn random_sample(
+    n: usize,
+    sizes: RangeInclusive<usize>,
+    samples: usize,
+    seed: u64,
+    is_valid: impl Fn(&[usize]) -> bool,
+) -> Vec<Vec<usize>> {
+    let mut rng = SplitMix64(seed);
+    let mut sampled = BTreeSet::new();
+    let mut rejections = 0;
+
+    let (min_k, max_k) = (*sizes.start(), (*sizes.end()).min(n));
+
+    if min_k > max_k {
+        return Vec::new();
+    }
+
+    let mut pool: Vec<usize> = (0..n).collect();
+
+    while sampled.len() < samples && rejections < MAX_REJECTIONS {
+        let k = min_k + rng.below(max_k - min_k + 1);
+
+        // Shuffle the first `k` features into place, then take them.
+        for i in 0..k {
+            let j = i + rng.below(n - i);
+            pool.swap(i, j);
+        }
+
+        let mut combo = pool[..k].to_vec();
+        combo.sort_unstable();
+
+        if !sampled.contains(&combo) && is_valid(&combo) {
+            sampled.insert(combo);
+            rejections = 0;
+        } else {
+            rejections += 1;
+        }
+    }
+
+    let mut sampled: Vec<Vec<usize>> = sampled.into_iter().collect();
+    sampled.sort_by_key(Vec::len);
+
+    sampled
+}
+
+/// Returns how many distinct pairs of features are enabled together in at least one combination,
+/// along with the total amount of pairs of `n` features.
+pub fn pair_coverage(
+    combos: impl IntoIterator<Item = Box<[FeatureKey]>>,
+    n: usize,
+) -> (usize, usize) {
+    let mut pairs = BTreeSet::new();
+
+    for combo in combos {
+        for (i, &a) in combo.iter().enumerate() {
+            for &b in &combo[i + 1..] {
+                pairs.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    (pairs.len(), n * n.saturating_sub(1) / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sample() {
+        let a = random_sample(20, 0..=20, 50, 42, |_| true);
+        let b = random_sample(20, 0..=20, 50, 42, |_| true);
+        let c = random_sample(20, 0..=20, 50, 43, |_| true);
+
+        assert_eq!(a.len(), 50);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn valid_and_unique() {
+        // Feature 0 is forbidden, and combinations may have at most 2 features.
+        let sample = random_sample(4, 0..=2, 100, 7, |combo| !combo.contains(&0));
+
+        // There are only 1 + 3 + 3 valid combinations.
+        assert_eq!(sample.len(), 7);
+        assert!(sample
+            .iter()
+            .all(|combo| combo.len() <= 2 && !combo.contains(&0)));
+        assert!(sample.windows(2).all(|w| w[0].len() <= w[1].len()));
+
+        // Only combinations of 2 or 3 features.
+        let sample = random_sample(4, 2..=3, 100, 7, |_| true);
+
+        assert_eq!(sample.len(), 6 + 4);
+        assert!(sample.iter().all(|combo| (2..=3).contains(&combo.len())));
+    }
+}
@@ -35,6 +35,67 @@ pub fn load_config(folder: &Path) -> anyhow::Result<WorkspaceConfig> {
         let config: schema::Config = toml::from_str(&contents)
             .with_context(|| format!("Failed to parse {path:?} as TOML."))?;
 
+        if let Some(commands) = &config.commands {
+            ensure!(
+                !commands.is_empty(),
+                "Config {path:?} must specify at least one command in `commands`."
+            );
+        }
+
+        if let Some(command) = &config.command {
+            ensure!(
+                !command.is_empty(),
+                "Config {path:?} must specify a program in `command`."
+            );
+            ensure!(
+                config.commands.is_none(),
+                "Config {path:?} cannot specify both `command` and `commands`. Please pick one."
+            );
+        }
+
+        if let Some(targets) = &config.targets {
+            ensure!(
+                !targets.is_empty(),
+                "Config {path:?} must specify at least one target in `targets`."
+            );
+        }
+
+        if let Some(toolchains) = &config.toolchains {
+            ensure!(
+                !toolchains.is_empty(),
+                "Config {path:?} must specify at least one toolchain in `toolchains`."
+            );
+        }
+
+        if let Some(t) = config.t {
+            ensure!(
+                (2..32).contains(&t),
+                "Config {path:?} must specify a `t` of at least 2 and at most 31."
+            );
+            ensure!(
+                config.strategy != Some(schema::Strategy::Exhaustive),
+                "Config {path:?} cannot specify `t` with `strategy = \"exhaustive\"`, since `t` only affects covering arrays."
+            );
+            ensure!(
+                config.strategy != Some(schema::Strategy::Random),
+                "Config {path:?} cannot specify `t` with `strategy = \"random\"`, since `t` only affects covering arrays."
+            );
+        }
+
+        if let (Some(min), Some(max)) = (config.min_combo_size, config.max_combo_size) {
+            ensure!(
+                min <= max,
+                "Config {path:?} must specify a `min_combo_size` no larger than `max_combo_size`."
+            );
+        }
+
+        if let Some(samples) = config.samples {
+            ensure!(
+                samples > 0,
+                "Config {path:?} must specify a `samples` of at least 1."
+            );
+        }
+
         if name == "global" {
             ensure!(
                 config.rules.is_empty(),
@@ -5,4 +5,4 @@ mod storage;
 
 pub use self::loader::load_config;
 pub use self::rule::Rule;
-pub use self::storage::{Config, WorkspaceConfig};
+pub use self::storage::{CheckBackend, ComboStrategy, Config, WorkspaceConfig};
@@ -7,6 +7,8 @@ pub struct Rule {
     when: FeatureExpr,
     require: FeatureExpr,
     forbid: FeatureExpr,
+    /// If specified, combinations matched by `when` are only checked on these toolchains.
+    toolchains: Option<Vec<String>>,
 }
 
 impl Rule {
@@ -45,6 +47,7 @@ impl Rule {
             when,
             require,
             forbid,
+            toolchains: schema.toolchains,
         }
     }
 
@@ -56,6 +59,19 @@ impl Rule {
             true
         }
     }
+
+    /// Returns true if a given combination may be checked on a toolchain according to this rule.
+    ///
+    /// [`None`] represents the default toolchain, which is never allowed by rules that specify
+    /// `toolchains`.
+    pub fn allows_toolchain(&self, combo: &[FeatureKey], toolchain: Option<&str>) -> bool {
+        match &self.toolchains {
+            Some(toolchains) if self.when.evaluate(combo) => {
+                toolchain.is_some_and(|toolchain| toolchains.iter().any(|t| t == toolchain))
+            }
+            _ => true,
+        }
+    }
 }
 
 /// A recursive expression of feature requirements.
@@ -156,8 +172,9 @@ mod tests {
                 when: TrueOrFeatureSet::True,
                 require: None,
                 forbid: None,
+                toolchains: None,
             },
-            &mut storage,
+            &storage,
         );
 
         assert_eq!(
@@ -166,6 +183,7 @@ mod tests {
                 when: FeatureExpr::Always,
                 require: FeatureExpr::Always,
                 forbid: FeatureExpr::Never,
+                toolchains: None,
             }
         );
 
@@ -174,8 +192,9 @@ mod tests {
                 when: TrueOrFeatureSet::True,
                 require: None,
                 forbid: Some(TrueOrFeatureSet::True),
+                toolchains: None,
             },
-            &mut storage,
+            &storage,
         );
 
         assert_eq!(
@@ -184,6 +203,7 @@ mod tests {
                 when: FeatureExpr::Always,
                 require: FeatureExpr::Always,
                 forbid: FeatureExpr::Always,
+                toolchains: None,
             }
         );
 
@@ -192,8 +212,9 @@ mod tests {
                 when: TrueOrFeatureSet::True,
                 require: None,
                 forbid: Some(TrueOrFeatureSet::FeatureSet(FeatureSet::Many(Vec::new()))),
+                toolchains: None,
             },
-            &mut storage,
+            &storage,
         );
 
         assert_eq!(
@@ -202,6 +223,7 @@ mod tests {
                 when: FeatureExpr::Always,
                 require: FeatureExpr::Always,
                 forbid: FeatureExpr::Never,
+                toolchains: None,
             }
         );
 
@@ -212,8 +234,9 @@ mod tests {
                 forbid: Some(TrueOrFeatureSet::FeatureSet(FeatureSet::One(
                     "baz".to_string(),
                 ))),
+                toolchains: None,
             },
-            &mut storage,
+            &storage,
         );
 
         assert_eq!(
@@ -222,6 +245,7 @@ mod tests {
                 when: FeatureExpr::Contains(foo),
                 require: FeatureExpr::Contains(bar),
                 forbid: FeatureExpr::Contains(baz),
+                toolchains: None,
             }
         );
     }
@@ -238,6 +262,7 @@ mod tests {
             when: FeatureExpr::Always,
             require: FeatureExpr::Always,
             forbid: FeatureExpr::Contains(foo),
+            toolchains: None,
         };
 
         assert!(!always_deny_foo.validate(&[foo]));
@@ -251,6 +276,7 @@ mod tests {
                 Box::new(FeatureExpr::Contains(baz)),
             ),
             forbid: FeatureExpr::Never,
+            toolchains: None,
         };
 
         assert!(require_bar_or_baz_when_foo.validate(&[]));
@@ -265,6 +291,7 @@ mod tests {
             ),
             require: FeatureExpr::Always,
             forbid: FeatureExpr::Always,
+            toolchains: None,
         };
 
         assert!(bar_baz_incompatible.validate(&[]));
@@ -273,6 +300,27 @@ mod tests {
         assert!(!bar_baz_incompatible.validate(&[bar, baz]));
     }
 
+    #[test]
+    fn rule_allows_toolchain() {
+        let mut storage = FeatureStorage::new();
+
+        let foo = storage.insert("foo".to_string(), &HashMap::new());
+        let bar = storage.insert("bar".to_string(), &HashMap::new());
+
+        let foo_only_on_nightly = Rule {
+            when: FeatureExpr::Contains(foo),
+            require: FeatureExpr::Always,
+            forbid: FeatureExpr::Never,
+            toolchains: Some(vec!["nightly".to_string()]),
+        };
+
+        assert!(foo_only_on_nightly.allows_toolchain(&[foo], Some("nightly")));
+        assert!(!foo_only_on_nightly.allows_toolchain(&[foo, bar], Some("stable")));
+        assert!(!foo_only_on_nightly.allows_toolchain(&[foo], None));
+        assert!(foo_only_on_nightly.allows_toolchain(&[bar], Some("stable")));
+        assert!(foo_only_on_nightly.allows_toolchain(&[], None));
+    }
+
     #[test]
     fn parse_expression() {
         fn expr_from_json(value: serde_json::Value, storage: &mut FeatureStorage) -> FeatureExpr {
@@ -1,20 +1,106 @@
 use serde::{de::Error, Deserialize, Deserializer};
+use std::fmt::{self, Display};
 
 /// Represents the configuration for a specific crate.
 #[derive(Deserialize, Default, Debug)]
 pub struct Config {
     pub max_combo_size: Option<usize>,
+    /// The minimum amount of features in a single combination.
+    pub min_combo_size: Option<usize>,
     pub skip_optional_deps: Option<bool>,
+    pub commands: Option<Vec<CheckCommand>>,
+    pub command: Option<Vec<String>>,
+    /// The timeout for checking a single combination, in seconds.
+    pub timeout: Option<u64>,
+    /// Extra arguments passed to every Cargo command.
+    pub cargo_args: Option<Vec<String>>,
+    /// Extra flags passed to `rustc` through the `RUSTFLAGS` environmental variable.
+    pub rustflags: Option<String>,
+    /// Configuration values passed to Cargo with `--config`.
+    pub cargo_config: Option<Vec<String>>,
+    /// Target triples that each combination is checked for, passed to Cargo with `--target`.
+    pub targets: Option<Vec<String>>,
+    /// Toolchains that each combination is checked with, passed to Cargo as `+toolchain`.
+    pub toolchains: Option<Vec<String>>,
+    /// Whether to check tests, examples, and benchmarks in addition to the library.
+    pub all_targets: Option<bool>,
+    /// How combinations of features are chosen.
+    pub strategy: Option<Strategy>,
+    /// The size of the groups of features whose states are all covered by a covering array.
+    pub t: Option<usize>,
+    /// How many combinations are checked by `strategy = "random"`.
+    pub samples: Option<usize>,
+    /// The seed that `strategy = "random"` chooses combinations with.
+    pub seed: Option<u64>,
+    /// Whether to check the crate's default features before any other combination.
+    pub check_default: Option<bool>,
+    /// Whether to check every feature enabled at once before any other combination.
+    pub check_all_features: Option<bool>,
+    /// The features that every combination is added to.
+    pub base: Option<Base>,
+    /// Whether to check the default features with each of them removed in turn.
+    pub leave_one_out: Option<bool>,
 
     #[serde(default, rename = "rule")]
     pub rules: Vec<Rule>,
 }
 
+/// A Cargo subcommand that is run for each feature combination.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckCommand {
+    /// `cargo check`
+    Check,
+    /// `cargo clippy -- -D warnings`
+    Clippy,
+    /// `cargo test --no-run`
+    Test,
+    /// `cargo doc --no-deps`
+    Doc,
+}
+
+impl Display for CheckCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Check => "check",
+            Self::Clippy => "clippy",
+            Self::Test => "test",
+            Self::Doc => "doc",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// How combinations of features are chosen.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Every combination up to `max_combo_size`.
+    Exhaustive,
+    /// A covering array, where every pair of features is enabled and disabled in each way.
+    Pairwise,
+    /// A fixed amount of random combinations of any size.
+    Random,
+}
+
+/// The features that every combination is added to.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Base {
+    /// No features, since combinations are checked with `--no-default-features`.
+    None,
+    /// The `default` feature, so combinations are checked on top of the default features.
+    Default,
+}
+
 #[derive(Deserialize, Clone, PartialEq, Debug)]
 pub struct Rule {
     pub when: TrueOrFeatureSet,
     pub require: Option<FeatureSet>,
     pub forbid: Option<TrueOrFeatureSet>,
+    /// If specified, combinations matched by `when` are only checked on these toolchains.
+    pub toolchains: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Clone, PartialEq, Debug)]
@@ -63,10 +149,29 @@ mod tests {
                 when: TrueOrFeatureSet::True,
                 require: None,
                 forbid: None,
+                toolchains: None,
             },
         );
     }
 
+    #[test]
+    fn check_command() {
+        let commands: Vec<CheckCommand> =
+            serde_json::from_value(json!(["check", "clippy", "test", "doc"])).unwrap();
+        assert_eq!(
+            commands,
+            [
+                CheckCommand::Check,
+                CheckCommand::Clippy,
+                CheckCommand::Test,
+                CheckCommand::Doc,
+            ],
+        );
+
+        let unknown: Result<CheckCommand, _> = serde_json::from_value(json!("build"));
+        assert!(unknown.is_err());
+    }
+
     #[test]
     fn true_or_feature_set() {
         let true_: TrueOrFeatureSet = serde_json::from_value(json!(true)).unwrap();
@@ -1,29 +1,99 @@
 use super::schema;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 #[derive(Default, Debug)]
 pub struct WorkspaceConfig {
     crates: HashMap<String, CrateConfig>,
 
     max_combo_size: Option<usize>,
+    min_combo_size: Option<usize>,
     skip_optional_deps: Option<bool>,
+    commands: Option<Vec<schema::CheckCommand>>,
+    command: Option<Vec<String>>,
+    timeout: Option<u64>,
+    cargo_args: Option<Vec<String>>,
+    rustflags: Option<String>,
+    cargo_config: Option<Vec<String>>,
+    targets: Option<Vec<String>>,
+    toolchains: Option<Vec<String>>,
+    all_targets: Option<bool>,
+    strategy: Option<schema::Strategy>,
+    t: Option<usize>,
+    samples: Option<usize>,
+    seed: Option<u64>,
+    check_default: Option<bool>,
+    check_all_features: Option<bool>,
+    base: Option<schema::Base>,
+    leave_one_out: Option<bool>,
+
+    /// The seed passed with `--seed`, which takes precedence over the config.
+    seed_override: Option<u64>,
+    /// The seed used if none is configured, chosen once per run.
+    default_seed: u64,
 }
 
 impl WorkspaceConfig {
     pub fn new(crates: HashMap<String, CrateConfig>, global: schema::Config) -> Self {
         let schema::Config {
             max_combo_size,
+            min_combo_size,
             skip_optional_deps,
+            commands,
+            command,
+            timeout,
+            cargo_args,
+            rustflags,
+            cargo_config,
+            targets,
+            toolchains,
+            all_targets,
+            strategy,
+            t,
+            samples,
+            seed,
+            check_default,
+            check_all_features,
+            base,
+            leave_one_out,
             rules: _,
         } = global;
 
         Self {
             crates,
             max_combo_size,
+            min_combo_size,
             skip_optional_deps,
+            commands,
+            command,
+            timeout,
+            cargo_args,
+            rustflags,
+            cargo_config,
+            targets,
+            toolchains,
+            all_targets,
+            strategy,
+            t,
+            samples,
+            seed,
+            check_default,
+            check_all_features,
+            base,
+            leave_one_out,
+            seed_override: None,
+            default_seed: 0,
         }
     }
 
+    /// Sets the seeds used by `strategy = "random"`.
+    ///
+    /// `seed_override` takes precedence over any configured seed, while `default_seed` is used if
+    /// there is neither.
+    pub fn set_seeds(&mut self, seed_override: Option<u64>, default_seed: u64) {
+        self.seed_override = seed_override;
+        self.default_seed = default_seed;
+    }
+
     pub fn get(&self, name: &str) -> Config<'_> {
         Config {
             workspace: self,
@@ -35,7 +105,25 @@ impl WorkspaceConfig {
 #[derive(Debug)]
 pub struct CrateConfig {
     max_combo_size: Option<usize>,
+    min_combo_size: Option<usize>,
     skip_optional_deps: Option<bool>,
+    commands: Option<Vec<schema::CheckCommand>>,
+    command: Option<Vec<String>>,
+    timeout: Option<u64>,
+    cargo_args: Option<Vec<String>>,
+    rustflags: Option<String>,
+    cargo_config: Option<Vec<String>>,
+    targets: Option<Vec<String>>,
+    toolchains: Option<Vec<String>>,
+    all_targets: Option<bool>,
+    strategy: Option<schema::Strategy>,
+    t: Option<usize>,
+    samples: Option<usize>,
+    seed: Option<u64>,
+    check_default: Option<bool>,
+    check_all_features: Option<bool>,
+    base: Option<schema::Base>,
+    leave_one_out: Option<bool>,
     rules: Vec<schema::Rule>,
 }
 
@@ -43,18 +131,75 @@ impl From<schema::Config> for CrateConfig {
     fn from(value: schema::Config) -> Self {
         let schema::Config {
             max_combo_size,
+            min_combo_size,
             skip_optional_deps,
+            commands,
+            command,
+            timeout,
+            cargo_args,
+            rustflags,
+            cargo_config,
+            targets,
+            toolchains,
+            all_targets,
+            strategy,
+            t,
+            samples,
+            seed,
+            check_default,
+            check_all_features,
+            base,
+            leave_one_out,
             rules,
         } = value;
 
         Self {
             max_combo_size,
+            min_combo_size,
             skip_optional_deps,
+            commands,
+            command,
+            timeout,
+            cargo_args,
+            rustflags,
+            cargo_config,
+            targets,
+            toolchains,
+            all_targets,
+            strategy,
+            t,
+            samples,
+            seed,
+            check_default,
+            check_all_features,
+            base,
+            leave_one_out,
             rules,
         }
     }
 }
 
+/// What is run to check each feature combination.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CheckBackend<'a> {
+    /// A list of Cargo subcommands, run in order.
+    Cargo(&'a [schema::CheckCommand]),
+    /// An external command template, with placeholders that are filled in for each combination.
+    Template(&'a [String]),
+}
+
+/// How combinations of features are chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComboStrategy {
+    /// Every combination up to the max combo size.
+    Exhaustive,
+    /// A covering array, where every `t` features are enabled and disabled in each way in at
+    /// least one combination.
+    Covering { t: usize },
+    /// Up to `samples` random combinations of any size, chosen with `seed`.
+    Random { samples: usize, seed: u64 },
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Config<'a> {
     workspace: &'a WorkspaceConfig,
@@ -69,6 +214,14 @@ impl<'a> Config<'a> {
             .or(self.workspace.max_combo_size)
     }
 
+    /// Defaults to 0.
+    pub fn min_combo_size(&self) -> usize {
+        self.crate_
+            .and_then(|c| c.min_combo_size)
+            .or(self.workspace.min_combo_size)
+            .unwrap_or_default()
+    }
+
     /// Defaults to false.
     pub fn skip_optional_deps(&self) -> bool {
         self.crate_
@@ -77,8 +230,210 @@ impl<'a> Config<'a> {
             .unwrap_or_default()
     }
 
+    /// Returns the command template if it is specified, else the Cargo commands.
+    ///
+    /// Crate configuration takes precedence over global configuration, even if they specify
+    /// different backends. Defaults to `commands = ["check"]`.
+    pub fn backend(&self) -> CheckBackend<'a> {
+        fn backend_of<'a>(
+            commands: &'a Option<Vec<schema::CheckCommand>>,
+            command: &'a Option<Vec<String>>,
+        ) -> Option<CheckBackend<'a>> {
+            match (commands, command) {
+                (_, Some(template)) => Some(CheckBackend::Template(template)),
+                (Some(commands), None) => Some(CheckBackend::Cargo(commands)),
+                (None, None) => None,
+            }
+        }
+
+        self.crate_
+            .and_then(|c| backend_of(&c.commands, &c.command))
+            .or_else(|| backend_of(&self.workspace.commands, &self.workspace.command))
+            .unwrap_or(CheckBackend::Cargo(&[schema::CheckCommand::Check]))
+    }
+
+    /// Defaults to [`None`], which never times out.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.crate_
+            .and_then(|c| c.timeout)
+            .or(self.workspace.timeout)
+            .map(Duration::from_secs)
+    }
+
+    /// Defaults to an empty slice.
+    pub fn cargo_args(&self) -> &'a [String] {
+        self.crate_
+            .and_then(|c| c.cargo_args.as_deref())
+            .or(self.workspace.cargo_args.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Defaults to [`None`].
+    pub fn rustflags(&self) -> Option<&'a str> {
+        self.crate_
+            .and_then(|c| c.rustflags.as_deref())
+            .or(self.workspace.rustflags.as_deref())
+    }
+
+    /// Defaults to an empty slice.
+    pub fn cargo_config(&self) -> &'a [String] {
+        self.crate_
+            .and_then(|c| c.cargo_config.as_deref())
+            .or(self.workspace.cargo_config.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Defaults to an empty slice, which only checks the host target.
+    pub fn targets(&self) -> &'a [String] {
+        self.crate_
+            .and_then(|c| c.targets.as_deref())
+            .or(self.workspace.targets.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Defaults to an empty slice, which only uses the default toolchain.
+    pub fn toolchains(&self) -> &'a [String] {
+        self.crate_
+            .and_then(|c| c.toolchains.as_deref())
+            .or(self.workspace.toolchains.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Defaults to false.
+    pub fn all_targets(&self) -> bool {
+        self.crate_
+            .and_then(|c| c.all_targets)
+            .or(self.workspace.all_targets)
+            .unwrap_or_default()
+    }
+
+    /// Defaults to [`ComboStrategy::Exhaustive`]. Specifying `t` uses a covering array of that
+    /// strength, while `strategy = "pairwise"` on its own is a covering array with `t = 2`.
+    /// `strategy = "random"` defaults to 100 samples, with the seed chosen for this run.
+    ///
+    /// `strategy` and `t` are resolved together, so crate configuration that specifies either of
+    /// them takes precedence over both in the global configuration.
+    pub fn strategy(&self) -> ComboStrategy {
+        fn strategy_of(
+            strategy: Option<schema::Strategy>,
+            t: Option<usize>,
+            random: impl FnOnce() -> ComboStrategy,
+        ) -> Option<ComboStrategy> {
+            match (strategy, t) {
+                (Some(schema::Strategy::Exhaustive), _) => Some(ComboStrategy::Exhaustive),
+                (Some(schema::Strategy::Random), _) => Some(random()),
+                (Some(schema::Strategy::Pairwise), t) => {
+                    Some(ComboStrategy::Covering { t: t.unwrap_or(2) })
+                }
+                (None, Some(t)) => Some(ComboStrategy::Covering { t }),
+                (None, None) => None,
+            }
+        }
+
+        let random = || ComboStrategy::Random {
+            samples: self
+                .crate_
+                .and_then(|c| c.samples)
+                .or(self.workspace.samples)
+                .unwrap_or(100),
+            seed: self
+                .workspace
+                .seed_override
+                .or(self.crate_.and_then(|c| c.seed))
+                .or(self.workspace.seed)
+                .unwrap_or(self.workspace.default_seed),
+        };
+
+        self.crate_
+            .and_then(|c| strategy_of(c.strategy, c.t, random))
+            .or_else(|| strategy_of(self.workspace.strategy, self.workspace.t, random))
+            .unwrap_or(ComboStrategy::Exhaustive)
+    }
+
+    /// Defaults to false.
+    pub fn check_default(&self) -> bool {
+        self.crate_
+            .and_then(|c| c.check_default)
+            .or(self.workspace.check_default)
+            .unwrap_or_default()
+    }
+
+    /// Defaults to false.
+    pub fn check_all_features(&self) -> bool {
+        self.crate_
+            .and_then(|c| c.check_all_features)
+            .or(self.workspace.check_all_features)
+            .unwrap_or_default()
+    }
+
+    /// Defaults to [`schema::Base::None`].
+    pub fn base(&self) -> schema::Base {
+        self.crate_
+            .and_then(|c| c.base)
+            .or(self.workspace.base)
+            .unwrap_or(schema::Base::None)
+    }
+
+    /// Defaults to false.
+    pub fn leave_one_out(&self) -> bool {
+        self.crate_
+            .and_then(|c| c.leave_one_out)
+            .or(self.workspace.leave_one_out)
+            .unwrap_or_default()
+    }
+
     /// Defaults to an empty slice.
     pub fn rules(&self) -> &[schema::Rule] {
         self.crate_.map_or(&[], |c| &c.rules)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_layers() {
+        let config = |strategy, t| schema::Config {
+            strategy,
+            t,
+            ..Default::default()
+        };
+
+        let strategy_of = |global: schema::Config, crate_: schema::Config| {
+            let crates = HashMap::from([("foo".to_string(), CrateConfig::from(crate_))]);
+            WorkspaceConfig::new(crates, global).get("foo").strategy()
+        };
+
+        assert_eq!(
+            strategy_of(config(None, None), config(None, None)),
+            ComboStrategy::Exhaustive
+        );
+
+        // The crate's `t` is not combined with the global strategy.
+        assert_eq!(
+            strategy_of(
+                config(Some(schema::Strategy::Random), None),
+                config(None, Some(3))
+            ),
+            ComboStrategy::Covering { t: 3 }
+        );
+
+        // The global `t` is not combined with the crate's strategy.
+        assert_eq!(
+            strategy_of(
+                config(None, Some(3)),
+                config(Some(schema::Strategy::Pairwise), None)
+            ),
+            ComboStrategy::Covering { t: 2 }
+        );
+
+        assert_eq!(
+            strategy_of(
+                config(Some(schema::Strategy::Pairwise), Some(4)),
+                config(None, None)
+            ),
+            ComboStrategy::Covering { t: 4 }
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Parsing for the JSON messages emitted by `cargo --message-format json`.
+//!
+//! See <https://doc.rust-lang.org/cargo/reference/external-tools.html#json-messages> for the full
+//! format. Only the subset needed by `flag-frenzy` is deserialized.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+/// A single error emitted by the compiler.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Diagnostic {
+    /// The error code, such as `E0425`, if there is one.
+    pub code: Option<String>,
+    /// The main message, such as "cannot find function `foo` in this scope".
+    pub message: String,
+    /// The location that caused the error, if there is one.
+    pub span: Option<Span>,
+}
+
+/// A location within a source file.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Span {
+    /// The path of the file, usually relative to the workspace root.
+    pub file_name: String,
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column number.
+    pub column: usize,
+}
+
+impl Diagnostic {
+    /// Returns true if both diagnostics are the same error at the same location, even if their
+    /// messages differ, such as when they name different types.
+    pub fn same_error(&self, other: &Diagnostic) -> bool {
+        self.code == other.code && self.span == other.span
+    }
+}
+
+impl Display for Diagnostic {
+    /// Formats the diagnostic in the same way as `--message-format short`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(Span {
+            file_name,
+            line,
+            column,
+        }) = &self.span
+        {
+            write!(f, "{file_name}:{line}:{column}: ")?;
+        }
+
+        match &self.code {
+            Some(code) => write!(f, "error[{code}]: {}", self.message),
+            None => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+/// The output of a Cargo command, split into errors and human-readable text.
+#[derive(Default, Debug)]
+pub struct ParsedOutput {
+    /// All errors emitted by the compiler, in order.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The rendered compiler messages, plus all lines that were not JSON messages.
+    pub rendered: Vec<u8>,
+}
+
+/// Parses the standard output of a Cargo command run with `--message-format json`.
+///
+/// Lines that are not JSON messages from Cargo are passed through to [`ParsedOutput::rendered`]
+/// unchanged, so this is safe to call on the output of any command.
+pub fn parse_output(stdout: &[u8]) -> ParsedOutput {
+    let mut output = ParsedOutput::default();
+
+    for line in stdout.split_inclusive(|&b| b == b'\n') {
+        let Ok(message) = serde_json::from_slice::<CargoMessage>(line) else {
+            output.rendered.extend_from_slice(line);
+            continue;
+        };
+
+        // Other messages, such as `compiler-artifact`, are not useful for humans.
+        let CargoMessage::CompilerMessage { message } = message else {
+            continue;
+        };
+
+        if let Some(rendered) = &message.rendered {
+            output.rendered.extend_from_slice(rendered.as_bytes());
+        }
+
+        if message.level.starts_with("error") && !message.is_summary() {
+            output.diagnostics.push(message.into());
+        }
+    }
+
+    output
+}
+
+/// A message emitted by Cargo, one per line.
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerMessage {
+        message: CompilerMessage,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    code: Option<CompilerCode>,
+    level: String,
+    spans: Vec<CompilerSpan>,
+    rendered: Option<String>,
+}
+
+impl CompilerMessage {
+    /// Returns true if this only summarizes the errors before it, such as "aborting due to 2
+    /// previous errors".
+    fn is_summary(&self) -> bool {
+        self.spans.is_empty()
+            && self.code.is_none()
+            && (self.message.starts_with("aborting due to")
+                || self.message.starts_with("could not compile"))
+    }
+}
+
+#[derive(Deserialize)]
+struct CompilerCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+}
+
+impl From<CompilerMessage> for Diagnostic {
+    fn from(value: CompilerMessage) -> Self {
+        let span = value
+            .spans
+            .into_iter()
+            .find(|span| span.is_primary)
+            .map(|span| Span {
+                file_name: span.file_name,
+                line: span.line_start,
+                column: span.column_start,
+            });
+
+        Self {
+            code: value.code.map(|c| c.code),
+            message: value.message,
+            span,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_compiler_error() {
+        let stdout = br#"{"reason":"compiler-message","message":{"rendered":"src/lib.rs:4:14: error[E0425]: cannot find function `missing` in this scope\n","children":[],"level":"error","message":"cannot find function `missing` in this scope","spans":[{"file_name":"src/lib.rs","is_primary":true,"line_start":4,"line_end":4,"column_start":14,"column_end":21}],"code":{"code":"E0425","explanation":null}}}
+{"reason":"compiler-message","message":{"rendered":"src/lib.rs:1:1: warning: unused\n","children":[],"level":"warning","message":"unused","spans":[],"code":null}}
+{"reason":"compiler-message","message":{"rendered":"error: aborting due to 1 previous error\n","children":[],"level":"error","message":"aborting due to 1 previous error","spans":[],"code":null}}
+{"reason":"build-finished","success":false}
+not json
+"#;
+
+        let output = parse_output(stdout);
+
+        assert_eq!(
+            output.diagnostics,
+            [Diagnostic {
+                code: Some("E0425".to_string()),
+                message: "cannot find function `missing` in this scope".to_string(),
+                span: Some(Span {
+                    file_name: "src/lib.rs".to_string(),
+                    line: 4,
+                    column: 14,
+                }),
+            }],
+        );
+
+        assert_eq!(
+            String::from_utf8(output.rendered).unwrap(),
+            "src/lib.rs:4:14: error[E0425]: cannot find function `missing` in this scope\nsrc/lib.rs:1:1: warning: unused\nerror: aborting due to 1 previous error\nnot json\n",
+        );
+    }
+
+    #[test]
+    fn display() {
+        let with_span = Diagnostic {
+            code: Some("E0433".to_string()),
+            message: "failed to resolve".to_string(),
+            span: Some(Span {
+                file_name: "src/window.rs".to_string(),
+                line: 10,
+                column: 5,
+            }),
+        };
+
+        assert_eq!(
+            with_span.to_string(),
+            "src/window.rs:10:5: error[E0433]: failed to resolve"
+        );
+
+        let without_span = Diagnostic {
+            code: None,
+            message: "linking with `cc` failed: exit status: 1".to_string(),
+            span: None,
+        };
+
+        assert_eq!(
+            without_span.to_string(),
+            "error: linking with `cc` failed: exit status: 1"
+        );
+    }
+
+    #[test]
+    fn same_error() {
+        let error = |code: &str, line| Diagnostic {
+            code: Some(code.to_string()),
+            message: "mismatched types".to_string(),
+            span: Some(Span {
+                file_name: "src/lib.rs".to_string(),
+                line,
+                column: 1,
+            }),
+        };
+
+        let other_message = Diagnostic {
+            message: "expected `u32`, found `u64`".to_string(),
+            ..error("E0308", 1)
+        };
+
+        assert!(error("E0308", 1).same_error(&other_message));
+        assert!(!error("E0308", 1).same_error(&error("E0308", 2)));
+        assert!(!error("E0308", 1).same_error(&error("E0425", 1)));
+    }
+}
@@ -0,0 +1,358 @@
+//! Comparing the JSON reports of two runs, for the `diff` subcommand.
+
+use crate::{
+    ansi::Color,
+    cli::DiffCLI,
+    report::PackageReport,
+    runner::{Outcome, Platform},
+};
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::BufReader,
+    path::Path,
+};
+
+/// The parts of a report written by `--report-json` that are compared.
+#[derive(Deserialize, Debug)]
+struct SavedReport {
+    packages: Vec<PackageReport>,
+    #[serde(default)]
+    unchecked_checks: usize,
+}
+
+impl SavedReport {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {path:?}."))?;
+
+        serde_json::from_reader(BufReader::new(file)).with_context(|| {
+            format!("Failed to parse {path:?}. Reports must be written with `--report-json`.")
+        })
+    }
+}
+
+/// A check within a report, identified by its package, features, and platform.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct CheckKey<'a> {
+    package: &'a str,
+    features: &'a [String],
+    toolchain: Option<&'a str>,
+    target: Option<&'a str>,
+}
+
+impl CheckKey<'_> {
+    fn platform(&self) -> Platform {
+        Platform {
+            toolchain: self.toolchain.map(str::to_string),
+            target: self.target.map(str::to_string),
+        }
+    }
+}
+
+/// What changed between two reports.
+#[derive(Default, Debug)]
+struct Diff<'a> {
+    /// Checks that passed in the old report, but fail in the new one.
+    newly_failing: Vec<(CheckKey<'a>, &'a Outcome)>,
+    /// Checks that failed in the old report, but pass in the new one.
+    newly_passing: Vec<CheckKey<'a>>,
+    /// Checks that are only in the new report, with their outcome.
+    added: Vec<(CheckKey<'a>, &'a Outcome)>,
+    /// Checks that are only in the old report.
+    removed: Vec<CheckKey<'a>>,
+    /// Packages whose amount of checks changed, with the old and new amount. A package that is
+    /// missing from either report has [`None`] instead.
+    check_counts: Vec<(&'a str, Option<usize>, Option<usize>)>,
+}
+
+impl Diff<'_> {
+    /// Returns the amount of checks that fail now, but did not before.
+    fn new_failures(&self) -> usize {
+        let added = self
+            .added
+            .iter()
+            .filter(|(_, outcome)| **outcome != Outcome::Passed)
+            .count();
+
+        self.newly_failing.len() + added
+    }
+}
+
+/// Compares the checks of two reports, sorted by package, features, and platform.
+fn diff<'a>(old: &'a SavedReport, new: &'a SavedReport) -> Diff<'a> {
+    fn checks(report: &SavedReport) -> BTreeMap<CheckKey<'_>, &Outcome> {
+        report
+            .packages
+            .iter()
+            .flat_map(|package| {
+                package.checks.iter().map(|check| {
+                    let key = CheckKey {
+                        package: &package.name,
+                        features: &check.features,
+                        toolchain: check.platform.toolchain.as_deref(),
+                        target: check.platform.target.as_deref(),
+                    };
+
+                    (key, &check.outcome)
+                })
+            })
+            .collect()
+    }
+
+    let old_checks = checks(old);
+    let new_checks = checks(new);
+
+    let mut diff = Diff::default();
+
+    for (key, &new_outcome) in &new_checks {
+        match old_checks.get(key) {
+            Some(&old_outcome) => {
+                let (old_passed, new_passed) = (
+                    *old_outcome == Outcome::Passed,
+                    *new_outcome == Outcome::Passed,
+                );
+
+                if old_passed && !new_passed {
+                    diff.newly_failing.push((key.clone(), new_outcome));
+                } else if !old_passed && new_passed {
+                    diff.newly_passing.push(key.clone());
+                }
+            }
+            None => diff.added.push((key.clone(), new_outcome)),
+        }
+    }
+
+    diff.removed = old_checks
+        .keys()
+        .filter(|key| !new_checks.contains_key(key))
+        .cloned()
+        .collect();
+
+    let counts = |report: &'a SavedReport| -> BTreeMap<&'a str, usize> {
+        report
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.actual_checks))
+            .collect()
+    };
+
+    let (old_counts, new_counts) = (counts(old), counts(new));
+    let names: BTreeSet<&str> = old_counts
+        .keys()
+        .chain(new_counts.keys())
+        .copied()
+        .collect();
+
+    for name in names {
+        let (old_count, new_count) = (old_counts.get(name), new_counts.get(name));
+
+        if old_count != new_count {
+            diff.check_counts
+                .push((name, old_count.copied(), new_count.copied()));
+        }
+    }
+
+    diff
+}
+
+/// Runs the `diff` subcommand, returning an error if any checks newly fail.
+pub fn main(cli: DiffCLI) -> anyhow::Result<()> {
+    let Color {
+        reset,
+        bold,
+        dim,
+        info,
+        success,
+        error,
+    } = Color::from_color_choice(cli.color);
+
+    let old = SavedReport::load(&cli.old).context("Failed to load the old report.")?;
+    let new = SavedReport::load(&cli.new).context("Failed to load the new report.")?;
+
+    let diff = diff(&old, &new);
+
+    let describe = |key: &CheckKey| {
+        format!(
+            "package {bold}{}{reset} with features {info}{:?}{reset}{}",
+            key.package,
+            key.features,
+            key.platform()
+        )
+    };
+
+    let outcome = |outcome: &Outcome| match outcome {
+        Outcome::Passed => format!("{success}passed{reset}"),
+        Outcome::Failed { command } => format!("{error}failed {command}{reset}"),
+        Outcome::TimedOut { command } => format!("{error}timed out running {command}{reset}"),
+    };
+
+    if !diff.newly_failing.is_empty() {
+        println!(
+            "{error}{bold}Newly failing: {}{reset}",
+            diff.newly_failing.len()
+        );
+
+        for (key, result) in &diff.newly_failing {
+            println!("\t{}: {}", describe(key), outcome(result));
+        }
+    }
+
+    if !diff.newly_passing.is_empty() {
+        println!(
+            "{success}{bold}Newly passing: {}{reset}",
+            diff.newly_passing.len()
+        );
+
+        for key in &diff.newly_passing {
+            println!("\t{}", describe(key));
+        }
+    }
+
+    if !diff.added.is_empty() {
+        println!("{bold}Added combos: {}{reset}", diff.added.len());
+
+        for (key, result) in &diff.added {
+            println!("\t{}: {}", describe(key), outcome(result));
+        }
+    }
+
+    if !diff.removed.is_empty() {
+        println!("{bold}Removed combos: {}{reset}", diff.removed.len());
+
+        for key in &diff.removed {
+            println!("\t{}", describe(key));
+        }
+    }
+
+    if !diff.check_counts.is_empty() {
+        println!("{bold}Changed check counts:{reset}");
+
+        for (name, old_count, new_count) in &diff.check_counts {
+            let count = |count: &Option<usize>| match count {
+                Some(count) => count.to_string(),
+                None => "not checked".to_string(),
+            };
+
+            println!(
+                "\tpackage {bold}{name}{reset}: {} -> {}",
+                count(old_count),
+                count(new_count)
+            );
+        }
+    }
+
+    // Checks that were never run show up as removed or added, even though nothing changed.
+    if old.unchecked_checks > 0 || new.unchecked_checks > 0 {
+        println!("{dim}One of the runs stopped early, so some combos may only appear to be added or removed.{reset}");
+    }
+
+    match diff.new_failures() {
+        0 => {
+            println!("{success}{bold}No new failures.{reset}");
+            Ok(())
+        }
+        n => bail!("{n} checks failed that did not fail before."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::CheckReport;
+
+    fn report(checks: &[(&[&str], bool)]) -> SavedReport {
+        let checks: Vec<CheckReport> = checks
+            .iter()
+            .map(|&(features, passed)| CheckReport {
+                features: features.iter().map(|f| f.to_string()).collect(),
+                platform: Platform::default(),
+                outcome: if passed {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed {
+                        command: "cargo-check".to_string(),
+                    }
+                },
+                cached: false,
+                duration_secs: None,
+            })
+            .collect();
+
+        SavedReport {
+            packages: vec![PackageReport {
+                name: "foo".to_string(),
+                features: Vec::new(),
+                estimated_checks: 0,
+                actual_checks: checks.len(),
+                checks,
+                skipped: Vec::new(),
+            }],
+            unchecked_checks: 0,
+        }
+    }
+
+    #[test]
+    fn compare_reports() {
+        let old = report(&[(&[], true), (&["a"], true), (&["b"], false), (&["c"], true)]);
+        let new = report(&[
+            (&[], true),
+            (&["a"], false),
+            (&["b"], true),
+            (&["d"], false),
+            (&["e"], true),
+        ]);
+
+        let diff = diff(&old, &new);
+        let features = |keys: Vec<&CheckKey>| -> Vec<Vec<String>> {
+            keys.into_iter().map(|k| k.features.to_vec()).collect()
+        };
+
+        assert_eq!(
+            features(diff.newly_failing.iter().map(|(k, _)| k).collect()),
+            [vec!["a"]],
+        );
+        assert_eq!(features(diff.newly_passing.iter().collect()), [vec!["b"]]);
+        assert_eq!(
+            features(diff.added.iter().map(|(k, _)| k).collect()),
+            [vec!["d"], vec!["e"]],
+        );
+        assert_eq!(features(diff.removed.iter().collect()), [vec!["c"]]);
+        assert_eq!(diff.check_counts, [("foo", Some(4), Some(5))]);
+
+        // `a` newly fails, and `d` is a new combo that fails.
+        assert_eq!(diff.new_failures(), 2);
+    }
+
+    #[test]
+    fn parse_json_report() {
+        let json = r#"{
+            "packages": [{
+                "name": "foo",
+                "features": ["bar"],
+                "estimated_checks": 2,
+                "actual_checks": 2,
+                "checks": [
+                    { "features": [], "outcome": { "kind": "passed" }, "cached": true },
+                    {
+                        "features": ["bar"],
+                        "toolchain": "nightly",
+                        "outcome": { "kind": "failed", "command": "cargo-check" },
+                        "cached": false,
+                        "duration_secs": 1.5
+                    }
+                ],
+                "skipped": [{ "features": ["bar"], "reason": "rule", "rule": 0 }]
+            }],
+            "failures": [],
+            "unchecked_checks": 0
+        }"#;
+
+        let report: SavedReport = serde_json::from_str(json).unwrap();
+        let checks = &report.packages[0].checks;
+
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[1].platform.toolchain.as_deref(), Some("nightly"));
+    }
+}
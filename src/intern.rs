@@ -33,6 +33,17 @@ pub struct FeatureStorage {
     ///
     /// This must be sorted based on the [`u64`], since lookups use binary search.
     inner: Vec<(u64, String, BTreeSet<FeatureKey>)>,
+    /// The entries of the `default` feature that can be passed to `--features`, in the order they
+    /// are listed.
+    default_features: Vec<FeatureKey>,
+    /// Entries of the `default` feature that are not features of the crate but can still be passed
+    /// to `--features`, such as `foo/bar`. The [`u64`] is the [`FeatureKey`].
+    ///
+    /// These are kept apart from `inner`, so that they are never combined with other features.
+    default_extras: Vec<(u64, String)>,
+    /// Entries of the `default` feature that enable optional dependencies with `dep:`, which Cargo
+    /// does not accept in `--features`.
+    default_dependencies: Vec<String>,
     /// The hashing state, used to calculate the hash (and thus the [`FeatureKey`]) of features.
     ///
     /// The hash of two identical values using the same [`RandomState`] will result in the same
@@ -51,6 +62,9 @@ impl FeatureStorage {
     pub fn new() -> Self {
         FeatureStorage {
             inner: Vec::new(),
+            default_features: Vec::new(),
+            default_extras: Vec::new(),
+            default_dependencies: Vec::new(),
             build_hasher: RandomState::new(),
         }
     }
@@ -61,18 +75,26 @@ impl FeatureStorage {
     pub fn with_capacity(capacity: usize) -> Self {
         FeatureStorage {
             inner: Vec::with_capacity(capacity),
+            default_features: Vec::new(),
+            default_extras: Vec::new(),
+            default_dependencies: Vec::new(),
             build_hasher: RandomState::new(),
         }
     }
 
     /// Retrieves a feature name from a key.
     ///
+    /// This also finds entries of [`Self::default_features()`] that are not features of the crate.
     /// This will return [`None`] if nothing is found.
     #[must_use]
     pub fn get(&self, key: FeatureKey) -> Option<&str> {
         match self.inner.binary_search_by_key(&key.0, |(h, ..)| *h) {
             Ok(i) => Some(&self.inner[i].1),
-            Err(_) => None,
+            Err(_) => self
+                .default_extras
+                .iter()
+                .find(|(h, _)| *h == key.0)
+                .map(|(_, feature)| feature.as_str()),
         }
     }
 
@@ -97,6 +119,25 @@ impl FeatureStorage {
             .unwrap_or_default()
     }
 
+    /// Returns the features that the `default` feature directly enables, in the order they are
+    /// listed.
+    ///
+    /// This includes entries that are not features of the crate, such as `foo/bar`, which are
+    /// never returned by [`Self::keys()`]. Entries like `dep:foo` are not included, see
+    /// [`Self::default_dependencies()`].
+    #[must_use]
+    pub fn default_features(&self) -> &[FeatureKey] {
+        &self.default_features
+    }
+
+    /// Returns the entries of the `default` feature that enable optional dependencies with `dep:`.
+    ///
+    /// These cannot be passed to Cargo with `--features`, so they are never enabled in checks.
+    #[must_use]
+    pub fn default_dependencies(&self) -> &[String] {
+        &self.default_dependencies
+    }
+
     /// Inserts a feature into storage, returning its key.
     pub fn insert(
         &mut self,
@@ -119,10 +160,8 @@ impl FeatureStorage {
                 dependencies_keys.insert(key);
 
                 if let Some(sub_dependencies) = self.get_dependencies(key) {
-                    dependencies_keys = dependencies_keys
-                        .union(sub_dependencies)
-                        .map(|val| *val)
-                        .collect();
+                    dependencies_keys =
+                        dependencies_keys.union(sub_dependencies).copied().collect();
                 }
             }
         }
@@ -190,6 +229,24 @@ pub fn intern_features(
         storage.insert(feature.clone(), &features);
     }
 
+    if let Some(defaults) = features.get("default") {
+        for feature in defaults {
+            if feature.starts_with("dep:") {
+                storage.default_dependencies.push(feature.clone());
+                continue;
+            }
+
+            let key = storage.create_key(feature);
+
+            // Features of dependencies, and optional dependencies skipped above.
+            if !features.contains_key(feature) {
+                storage.default_extras.push((key.0, feature.clone()));
+            }
+
+            storage.default_features.push(key);
+        }
+    }
+
     storage
 }
 
@@ -221,9 +278,9 @@ mod tests {
             .map(|i| storage.insert(i.to_string(), &HashMap::new()))
             .collect();
 
-        for i in 0..5 {
+        for (i, &key) in keys.iter().enumerate() {
             assert_eq!(
-                storage.get(keys[i]).unwrap(),
+                storage.get(key).unwrap(),
                 i.to_string(),
                 "The feature returned by `FeatureStorage::get()` is incorrect."
             );
@@ -345,8 +402,10 @@ mod tests {
         features_map.insert("foobar".to_string(), vec!["foo".to_string()]);
         features_map.insert("foo".to_string(), vec!["dep:foo".to_string()]);
 
-        let mut schema_config = crate::config::schema::Config::default();
-        schema_config.skip_optional_deps = Some(true);
+        let schema_config = crate::config::schema::Config {
+            skip_optional_deps: Some(true),
+            ..Default::default()
+        };
         let workspace_config = WorkspaceConfig::new(HashMap::new(), schema_config);
         let storage = intern_features(features_map, workspace_config.get(""));
 
@@ -359,4 +418,37 @@ mod tests {
         assert_eq!(storage.get(foo_key), None);
         assert_eq!(storage.get(dep_foo_key), None);
     }
+
+    #[test]
+    fn default_entries() {
+        let mut features_map = HashMap::new();
+        features_map.insert(
+            "default".to_string(),
+            vec![
+                "std".to_string(),
+                "dep:foo".to_string(),
+                "bar/baz".to_string(),
+            ],
+        );
+        features_map.insert("std".to_string(), Vec::new());
+
+        let schema_config = crate::config::schema::Config::default();
+        let workspace_config = WorkspaceConfig::new(HashMap::new(), schema_config);
+        let storage = intern_features(features_map, workspace_config.get(""));
+
+        let defaults: Vec<_> = storage
+            .default_features()
+            .iter()
+            .map(|&key| storage.get(key).unwrap())
+            .collect();
+
+        assert_eq!(defaults, ["std", "bar/baz"]);
+        assert_eq!(storage.default_dependencies(), ["dep:foo"]);
+
+        // Features of dependencies are never combined with other features.
+        assert_eq!(storage.len(), 2);
+        assert!(storage
+            .keys()
+            .all(|key| storage.get(key) != Some("bar/baz")));
+    }
 }
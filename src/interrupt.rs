@@ -0,0 +1,28 @@
+//! Graceful handling of Ctrl-C.
+
+use anyhow::Context;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set to true once Ctrl-C has been pressed.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs a Ctrl-C handler that sets [`is_interrupted()`] instead of exiting immediately.
+///
+/// This gives the main loop a chance to stop cleanly and print what it has found so far. If Ctrl-C
+/// is pressed a second time, the process exits immediately.
+pub fn install_handler() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            // The user is impatient, so exit with the conventional code for SIGINT.
+            std::process::exit(130);
+        }
+
+        eprintln!("Interrupted, waiting for running checks to finish. Press Ctrl-C again to exit immediately.");
+    })
+    .context("Failed to install Ctrl-C handler.")
+}
+
+/// Returns true if Ctrl-C has been pressed.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
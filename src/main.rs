@@ -1,32 +1,72 @@
 mod ansi;
+mod cache;
+mod checkpoint;
 mod chunk;
 mod cli;
+mod cluster;
 mod combos;
 mod config;
+mod diagnostics;
+mod diff;
 mod intern;
+mod interrupt;
 mod manifest;
+mod minimize;
+mod pool;
+mod process;
+mod progress;
+mod report;
 mod runner;
+mod rustup;
+mod suggest;
 
 use ansi::*;
-use anyhow::{bail, Context};
+use anyhow::{bail, ensure, Context};
+use cache::{hash_sources, source_dirs, PackageCache};
+use checkpoint::Checkpoint;
 use chunk::select_chunk;
-use cli::CLI;
-use combos::{estimate_combos, feature_combos};
-use config::{load_config, WorkspaceConfig};
-use intern::intern_features;
+use cli::{DiffCLI, CLI};
+use cluster::cluster_failures;
+use combos::{
+    all_combos, baselines, estimate_combos, leave_one_out, pair_coverage, platform_combos,
+    Baseline, ComboPool, PlatformCombo,
+};
+use config::{load_config, ComboStrategy, Config, Rule, WorkspaceConfig};
+use diagnostics::Diagnostic;
+use intern::{intern_features, FeatureKey, FeatureStorage};
+use interrupt::is_interrupted;
 use manifest::{load_manifest, Manifest, Package};
-use runner::check_with_features;
-use std::path::Path;
+use pool::run_ordered;
+use progress::Progress;
+use report::{CheckReport, PackageReport, Report, SkippedCombo};
+use runner::{check_with_features, rustc_version, CheckOptions, Outcome, Platform};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env,
+    hash::{BuildHasher, Hasher, RandomState},
+    io::{self, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
 
 fn main() -> anyhow::Result<()> {
+    if let Some(cli) = DiffCLI::from_env() {
+        return diff::main(cli);
+    }
+
     let cli = CLI::from_env().context("Failed to verify CLI flags.")?;
 
+    interrupt::install_handler()?;
+
     let config_path = match cli.config {
         Some(ref path) => path,
         None => Path::new("config"),
     };
 
-    let config = if config_path.is_dir() {
+    let mut config = if config_path.is_dir() {
         load_config(config_path)
             .with_context(|| format!("Failed to load config from {config_path:?}."))?
     } else {
@@ -34,44 +74,405 @@ fn main() -> anyhow::Result<()> {
         WorkspaceConfig::default()
     };
 
+    let color = Color::from_color_choice(cli.color);
+
     let Color {
         reset,
         bold,
-        dim,
         info,
         success,
         error,
-    } = Color::from_color_choice(cli.color);
+        ..
+    } = color;
 
     let manifest = load_manifest(&cli.manifest_path).context("Failed to load Cargo manifest.")?;
 
+    // When running checks in parallel, each worker gets its own target directory so that they do
+    // not block each other on Cargo's build directory lock.
+    let target_dirs: Vec<Option<PathBuf>> = (0..cli.jobs)
+        .map(|worker| {
+            (cli.jobs > 1).then(|| {
+                manifest
+                    .target_directory
+                    .join("flag-frenzy")
+                    .join(worker.to_string())
+            })
+        })
+        .collect();
+
+    // The directories that affect each package are only needed to hash sources for the cache.
+    let cache = cli
+        .cache_dir
+        .as_deref()
+        .map(|cache_dir| (cache_dir, source_dirs(&manifest)));
+
+    let workspace_root = manifest.workspace_root.clone();
+
     let packages =
         process_packages(manifest, &cli, &config).context("Failure while processing packages.")?;
 
-    let mut failures = Vec::new();
+    let package_names: Vec<String> = packages.iter().map(|p| p.name.clone()).collect();
+
+    // If resuming, new checkpoints are written to the same file unless another one is specified.
+    let checkpoint_path = cli.checkpoint.as_deref().or(cli.resume.as_deref());
+
+    // The checkpoint tracks the progress of this run, even if it is not saved.
+    let mut checkpoint = match cli.resume {
+        Some(ref path) => Checkpoint::load(path, &package_names)
+            .with_context(|| format!("Failed to resume from {path:?}."))?,
+        None => Checkpoint {
+            packages: package_names,
+            ..Default::default()
+        },
+    };
+
+    // Random combos are chosen with a new seed each run, unless one is configured. It is saved in
+    // the checkpoint so a resumed run chooses the same combos.
+    let default_seed = *checkpoint
+        .seed
+        .get_or_insert_with(|| RandomState::new().build_hasher().finish());
+
+    config.set_seeds(cli.seed, default_seed);
+
+    // Validate every package before checking anything, rather than failing after the packages
+    // before it were already checked. `rustup` is only queried once a package that specifies
+    // `toolchains` or `targets` is found.
+    let mut installed = rustup::Installed::default();
+
+    for package in &packages {
+        // Packages that were already checked before resuming are skipped anyway.
+        if checkpoint.progress_of(&package.name).is_none() {
+            continue;
+        }
+
+        let package_config = config.get(&package.name);
+
+        for platform in platforms(package_config) {
+            installed
+                .verify(&platform)
+                .with_context(|| format!("Unable to check package {}{platform}.", package.name))?;
+        }
+
+        // Cargo only accepts `--target-dir` once, so it would conflict with each job's own.
+        if cli.jobs > 1
+            && package_config
+                .cargo_args()
+                .iter()
+                .chain(&cli.cargo_args)
+                .any(|arg| arg == "--target-dir" || arg.starts_with("--target-dir="))
+        {
+            bail!("Package {} passes `--target-dir` to Cargo, which cannot be used with `--jobs` greater than 1, since each job uses its own target directory.", package.name);
+        }
+    }
+
+    let mut package_reports = Vec::new();
+
+    // Set if checking stopped before all packages were checked.
+    let mut stopped = None;
+    let mut unchecked_combos = 0;
+
+    // Counting every check up front requires generating all combos, so only do so if needed.
+    let progress = cli.progress.then(|| {
+        let total = packages
+            .iter()
+            .filter_map(|package| {
+                let completed_combos = checkpoint.progress_of(&package.name)?;
+                Some(count_combos(package, &config).saturating_sub(completed_combos))
+            })
+            .sum();
+
+        Progress::new(total, cli.jobs)
+    });
+
+    let run = Run {
+        cli: &cli,
+        config: &config,
+        color,
+        target_dirs: &target_dirs,
+        cache: cache
+            .as_ref()
+            .map(|(cache_dir, source_dirs)| (*cache_dir, source_dirs)),
+        workspace_root: &workspace_root,
+        checkpoint_path,
+        progress: progress.as_ref(),
+    };
+
+    let mut packages = packages.into_iter();
+
+    for package in packages.by_ref() {
+        let Some(completed_combos) = checkpoint.progress_of(&package.name) else {
+            println!(
+                "{bold}Package {info}{}{reset}{bold} was already checked.{reset}",
+                package.name
+            );
+            continue;
+        };
+
+        let package_run = check_package(&run, package, completed_combos, &mut checkpoint)?;
+
+        package_reports.extend(package_run.report);
+
+        if package_run.stopped.is_some() {
+            stopped = package_run.stopped;
+            unchecked_combos += package_run.unchecked_combos;
+            break;
+        }
+    }
+
+    // Count the combos of all packages that were never started.
+    if stopped.is_some() {
+        for package in packages {
+            if let Some(completed_combos) = checkpoint.progress_of(&package.name) {
+                unchecked_combos +=
+                    count_combos(&package, &config).saturating_sub(completed_combos);
+            }
+        }
+    }
+
+    let failures = checkpoint.failures;
+
+    print_failure_report(&failures, cli.verbose, color);
+
+    if cli.suggest_rules {
+        print_suggested_rules(&package_reports, color);
+    }
+
+    let report = Report {
+        packages: package_reports,
+        failures: &failures,
+        unchecked_checks: unchecked_combos,
+    };
+
+    if let Some(ref path) = cli.report_json {
+        report::json::write(&report, path)?;
+        println!("{bold}Wrote JSON report to {info}{}{reset}", path.display());
+    }
 
-    for package in packages {
-        let Package { name, features } = package;
-        let package_config = config.get(&name);
-        let storage = intern_features(features, package_config);
+    if let Some(ref path) = cli.report_junit {
+        report::junit::write(&report, path)?;
+        println!(
+            "{bold}Wrote JUnit report to {info}{}{reset}",
+            path.display()
+        );
+    }
+
+    if let Some(ref path) = cli.report_sarif {
+        report::sarif::write(&report, path)?;
+        println!(
+            "{bold}Wrote SARIF report to {info}{}{reset}",
+            path.display()
+        );
+    }
+
+    if let Some(ref path) = cli.report_html {
+        report::html::write(&report, path)?;
+        println!("{bold}Wrote HTML report to {info}{}{reset}", path.display());
+    }
 
-        // The number of features or the max combo size, whichever is smaller.
-        let max_k = package_config.max_combo_size();
+    match stopped {
+        Some(Stop::Interrupted) => {
+            eprintln!(
+                "{error}{bold}Interrupted, leaving {unchecked_combos} checks unchecked.{reset}"
+            );
+
+            match checkpoint_path {
+                Some(path) => bail!("Interrupted before all packages were checked. Continue where this run stopped with `--resume {}`.", path.display()),
+                None => bail!("Interrupted before all packages were checked."),
+            }
+        }
+        Some(Stop::MaxFailures) => {
+            eprintln!("{error}{bold}Stopped after {} failures, leaving {unchecked_combos} checks unchecked.{reset}", failures.len());
+        }
+        None => {}
+    }
+
+    if !failures.is_empty() {
+        bail!("Some packages failed to be checked.");
+    }
+
+    if cli.dry_run {
+        println!("{info}{bold}Dry run completed, no checks were run.{reset}");
+    } else {
+        println!("{success}{bold}Feature combination checks successful! Congrats :){reset}");
+    }
+
+    Ok(())
+}
 
-        let estimated_checks = estimate_combos(storage.len() as u128, max_k.map(|k| k as u128))
+/// Checks every combo of a single package that was not already checked, starting after the first
+/// `completed_combos`.
+///
+/// Failures are added to the `checkpoint`, which is saved after every check if enabled.
+fn check_package(
+    run: &Run<'_>,
+    package: Package,
+    completed_combos: usize,
+    checkpoint: &mut Checkpoint,
+) -> anyhow::Result<PackageRun> {
+    let Run {
+        cli,
+        config,
+        color:
+            Color {
+                reset,
+                bold,
+                dim,
+                info,
+                error,
+                ..
+            },
+        target_dirs,
+        cache,
+        workspace_root,
+        checkpoint_path,
+        progress,
+    } = *run;
+
+    let Package {
+        name,
+        features,
+        targets,
+        ..
+    } = package;
+
+    let package_config = config.get(&name);
+    let storage = intern_features(features, package_config);
+
+    // The number of features or the max combo size, whichever is smaller.
+    let max_k = package_config.max_combo_size();
+
+    let platforms = platforms(package_config);
+
+    // Flags from the config are added to any that are already set in the environment. Flags
+    // from the environment are kept even without any in the config, so they are part of the
+    // cache key.
+    let existing = env::var("RUSTFLAGS").ok().filter(|flags| !flags.is_empty());
+    let rustflags = match (existing, package_config.rustflags()) {
+        (Some(existing), Some(flags)) => Some(format!("{existing} {flags}")),
+        (existing, flags) => existing.or(flags.map(str::to_string)),
+    };
+
+    let options = CheckOptions {
+        backend: package_config.backend(),
+        package: &name,
+        manifest_path: &cli.manifest_path,
+        // The CLI's timeout takes precedence over the config.
+        timeout: cli
+            .timeout
+            .map(Duration::from_secs)
+            .or(package_config.timeout()),
+        // Arguments from the CLI are passed after those from the config.
+        cargo_args: package_config
+            .cargo_args()
+            .iter()
+            .chain(&cli.cargo_args)
+            .cloned()
+            .collect(),
+        cargo_config: package_config.cargo_config(),
+        rustflags,
+        all_targets: package_config.all_targets().then_some(&targets[..]),
+    };
+
+    // The features that combos are made from, which leaves out the default features if combos
+    // are added to them.
+    let pool = ComboPool::new(&storage, package_config);
+
+    let estimated_combos = match package_config.strategy() {
+        ComboStrategy::Exhaustive => estimate_combos(pool.features.len() as u128, package_config.min_combo_size() as u128, max_k.map(|k| k as u128))
             .context("Consider decreasing the max combo size in the config.")
-            .with_context(|| format!("Total features: {}, Max combo size: {max_k:?}", storage.len()))
-            .with_context(|| format!("Unable to estimate checks required for all feature combinations of package {name}."))?;
+            .with_context(|| format!("Total features: {}, Max combo size: {max_k:?}", pool.features.len()))
+            .with_context(|| format!("Unable to estimate checks required for all feature combinations of package {name}."))?,
+        // Covering arrays are already built by the pool, so they can be counted exactly.
+        ComboStrategy::Covering { .. } => pool.rows.as_ref().map_or(0, Vec::len) as u128,
+        // There may be fewer valid combos than samples, though this is rare.
+        ComboStrategy::Random { samples, .. } => samples as u128,
+    };
+
+    let baselines = baselines(&storage, package_config);
+    let left_out = leave_one_out(&storage, package_config).len();
+
+    let estimated_checks = estimated_combos
+        // Baselines may also be among the other combos, in which case they are only checked once.
+        .saturating_add(baselines.len() as u128)
+        .saturating_add(left_out as u128)
+        // Every combo is checked once per platform.
+        .saturating_mul(platforms.len() as u128);
+
+    println!(
+        "{bold}Package {info}{name}{reset}{bold} with {info}{}{reset}{bold} features.{reset}",
+        storage.len()
+    );
+    println!("{bold}Estimated checks: {info}{estimated_checks}{reset}");
 
+    if let ComboStrategy::Random { samples, seed } = package_config.strategy() {
+        println!("{bold}Sampling {info}{samples}{reset}{bold} combos with seed {info}{seed}{reset}{bold}.{reset}");
+    }
+
+    if left_out > 0 && !storage.default_dependencies().is_empty() {
         println!(
-            "{bold}Package {info}{name}{reset}{bold} with {info}{}{reset}{bold} features.{reset}",
-            storage.len()
+            "{bold}Disabling {info}{:?}{reset}{bold} in leave-one-out checks, since Cargo does not accept `dep:` in `--features`.{reset}",
+            storage.default_dependencies()
         );
-        println!("{bold}Estimated checks: {info}{estimated_checks}{reset}");
+    }
+
+    // Each platform has its own cache, since a combo may pass on one but not another.
+    let package_caches = match cache {
+        Some((cache_dir, source_dirs)) if !cli.dry_run => {
+            let source_hash = hash_sources(&source_dirs[&name], workspace_root, cache_dir)
+                .with_context(|| format!("Failed to hash sources of package {name}."))?;
+
+            platforms
+                .iter()
+                .map(|platform| {
+                    let key = options.cache_key(platform, &rustc_version(platform)?);
+
+                    PackageCache::open(cache_dir, &name, source_hash, &key)
+                        .map(Some)
+                        .with_context(|| {
+                            format!("Failed to open cache for package {name} in {cache_dir:?}.")
+                        })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        }
+        _ => platforms.iter().map(|_| None).collect(),
+    };
+
+    if let Some(progress) = progress {
+        let total = platform_combos(&storage, package_config, &pool, &platforms).count();
+        progress.start_package(&name, completed_combos, total);
+    }
+
+    if completed_combos > 0 {
+        // Checks are resumed by their index, so the config must still generate the same checks.
+        let last_check = platform_combos(&storage, package_config, &pool, &platforms)
+            .nth(completed_combos - 1)
+            .map(|(combo, i)| {
+                let mut features: Vec<String> = combo
+                    .iter()
+                    .map(|&key| storage.get(key).unwrap().to_string())
+                    .collect();
+
+                features.sort_unstable();
+
+                (features, platforms[i].clone())
+            });
+
+        ensure!(
+            last_check.is_some()
+                && (checkpoint.last_check.is_none() || last_check == checkpoint.last_check),
+            "Package {name} generates different checks than when the checkpoint was saved. Make sure to use the same config and options as the original run."
+        );
+
+        println!("{bold}Resuming after {info}{completed_combos}{reset}{bold} checks.{reset}");
+    }
+
+    let checks = platform_combos(&storage, package_config, &pool, &platforms)
+        // Skip checks that were completed before the run was resumed.
+        .skip(completed_combos)
+        .map(|(combo, i)| {
+            let platform = &platforms[i];
+            let cache = package_caches[i].as_ref();
 
-        let mut actual_checks = 0;
-        for combo in feature_combos(&storage, package_config) {
-            actual_checks += 1;
             let mut features = Vec::with_capacity(combo.len());
 
             for &key in combo.iter() {
@@ -80,41 +481,803 @@ fn main() -> anyhow::Result<()> {
 
             features.sort_unstable();
 
-            println!("\t{dim}Checking:{reset} {info}{:?}{reset}", features);
+            // Skip combos that passed in a previous run with the same sources.
+            let cached = cache.is_some_and(|cache| cache.contains(&features));
 
-            if cli.dry_run {
-                continue;
+            // Other combos with the same features as a baseline are never generated.
+            let baseline = baselines
+                .iter()
+                .find(|(_, baseline)| *baseline == combo)
+                .map(|&(baseline, _)| baseline);
+
+            Check {
+                combo,
+                features,
+                platform,
+                cache,
+                cached,
+                baseline,
             }
-            let status = check_with_features(&name, &cli.manifest_path, &combo, &storage)
-                .with_context(|| format!("Tried checking package {name}."))?;
+        });
 
-            if !status.success() {
-                failures.push(CheckFailure {
-                    package: name.clone(),
-                    features: features.into_iter().map(str::to_string).collect(),
+    // Marks the checks of baselines, so they stand out from the others.
+    let label = |baseline: Option<Baseline>| match baseline {
+        Some(baseline) => format!(" {bold}({baseline} baseline){reset}"),
+        None => String::new(),
+    };
+
+    let mut feature_names: Vec<String> = storage
+        .keys()
+        .filter_map(|key| storage.get(key))
+        .map(str::to_string)
+        .collect();
+
+    feature_names.sort_unstable();
+
+    let mut package_report = PackageReport {
+        name: name.clone(),
+        features: feature_names,
+        estimated_checks,
+        actual_checks: 0,
+        checks: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    let mut actual_checks = completed_combos;
+    let mut report = None;
+    let mut stopped = None;
+    let mut cached_checks = 0;
+    // Failures of this package from before the run was resumed count towards
+    // `--max-package-failures`, just like all failures in the checkpoint count towards
+    // `--max-failures`.
+    let mut package_failures = checkpoint
+        .failures
+        .iter()
+        .filter(|failure| failure.package == name)
+        .count();
+
+    if cli.dry_run {
+        for Check {
+            features,
+            platform,
+            baseline,
+            ..
+        } in checks
+        {
+            actual_checks += 1;
+            println!(
+                "\t{dim}Checking:{reset} {info}{features:?}{reset}{platform}{}",
+                label(baseline)
+            );
+        }
+    } else {
+        // The outcome of every check, so `--minimize` does not repeat them.
+        let mut outcomes = Outcomes::new();
+
+        // Checks may finish in any order, but their results are always reported in the order
+        // the combos were generated.
+        let flow = run_ordered(
+            cli.jobs,
+            checks,
+            |worker, check| {
+                if check.cached {
+                    return None;
+                }
+
+                if let Some(progress) = progress {
+                    progress.start_check(format_args!("{:?}{}", check.features, check.platform));
+                }
+
+                let result = check_with_features(
+                    &options,
+                    check.platform,
+                    target_dirs[worker].as_deref(),
+                    &check.combo,
+                    &storage,
+                );
+
+                Some(result)
+            },
+            |Check {
+                 features,
+                 platform,
+                 cache,
+                 baseline,
+                 ..
+             },
+             result| {
+                // Hide the status line while printing the result.
+                let mut progress = progress.map(Progress::suspend);
+
+                // Discard the result, since the check may have been interrupted as well.
+                if is_interrupted() {
+                    stopped = Some(Stop::Interrupted);
+                    return Ok(ControlFlow::Break(()));
+                }
+
+                actual_checks += 1;
+                checkpoint.complete_combo(&name, &features, platform);
+
+                let Some(result) = result else {
+                    cached_checks += 1;
+                    println!(
+                        "\t{dim}Cached:{reset} {info}{features:?}{reset}{platform}{}",
+                        label(baseline)
+                    );
+
+                    if let Some(ref mut progress) = progress {
+                        progress.finish_check(format_args!("{features:?}{platform}"), None);
+                    }
+
+                    package_report.checks.push(CheckReport {
+                        features: features.iter().map(|f| f.to_string()).collect(),
+                        platform: platform.clone(),
+                        outcome: Outcome::Passed,
+                        cached: true,
+                        duration_secs: None,
+                    });
+
+                    if cli.minimize {
+                        outcomes.insert((features, platform), (Outcome::Passed, None));
+                    }
+
+                    if let Some(path) = checkpoint_path {
+                        checkpoint.save(path)?;
+                    }
+
+                    return Ok(ControlFlow::Continue(()));
+                };
+
+                let result = result.with_context(|| format!("Tried checking package {name}."))?;
+
+                match progress {
+                    Some(ref mut progress) => {
+                        println!(
+                            "\t{dim}Checking:{reset} {info}{features:?}{reset}{platform}{} {dim}({:.1}s){reset}",
+                            label(baseline),
+                            result.duration.as_secs_f64()
+                        );
+
+                        progress.finish_check(
+                            format_args!("{features:?}{platform}"),
+                            Some(result.duration),
+                        );
+                    }
+                    None => {
+                        println!(
+                            "\t{dim}Checking:{reset} {info}{features:?}{reset}{platform}{}",
+                            label(baseline)
+                        )
+                    }
+                }
+
+                // Print Cargo's output after the combo it belongs to, so that the output of
+                // parallel checks does not get mixed together.
+                io::stdout().write_all(&result.stdout)?;
+                io::stderr().write_all(&result.stderr)?;
+
+                package_report.checks.push(CheckReport {
+                    features: features.iter().map(|f| f.to_string()).collect(),
+                    platform: platform.clone(),
+                    outcome: result.outcome.clone(),
+                    cached: false,
+                    duration_secs: Some(result.duration.as_secs_f64()),
                 });
+
+                if cli.minimize {
+                    outcomes.insert(
+                        (features.clone(), platform),
+                        (result.outcome.clone(), result.diagnostics.first().cloned()),
+                    );
+                }
+
+                if result.outcome == Outcome::Passed {
+                    if let Some(cache) = cache {
+                        cache.insert(&features).with_context(|| {
+                            format!("Failed to cache result for package {name}.")
+                        })?;
+                    }
+                } else {
+                    let mut diagnostics = result.diagnostics;
+                    diagnostics.truncate(cli.max_diagnostics);
+
+                    if let Some(baseline) = baseline {
+                        eprintln!("{error}{bold}The {baseline} baseline of package {name} failed, which may cause many other combos to fail.{reset}");
+                    }
+
+                    checkpoint.failures.push(CheckFailure {
+                        package: name.clone(),
+                        platform: platform.clone(),
+                        features: features.into_iter().map(str::to_string).collect(),
+                        outcome: result.outcome,
+                        diagnostics,
+                        minimized: None,
+                        baseline,
+                    });
+
+                    package_failures += 1;
+                }
+
+                if let Some(path) = checkpoint_path {
+                    checkpoint.save(path)?;
+                }
+
+                if cli
+                    .max_failures
+                    .is_some_and(|max| checkpoint.failures.len() >= max)
+                {
+                    stopped = Some(Stop::MaxFailures);
+                    return Ok(ControlFlow::Break(()));
+                }
+
+                if cli
+                    .max_package_failures
+                    .is_some_and(|max| package_failures >= max)
+                {
+                    return Ok(ControlFlow::Break(()));
+                }
+
+                anyhow::Ok(ControlFlow::Continue(()))
+            },
+        )?;
+
+        if let Some(progress) = progress {
+            progress.finish_package();
+        }
+
+        if cli.minimize && !matches!(stopped, Some(Stop::Interrupted)) {
+            let minimize_flow = minimize_failures(
+                run,
+                &options,
+                &storage,
+                package_config,
+                &platforms,
+                outcomes,
+                checkpoint,
+            )?;
+
+            // Minimizing only stops early when interrupted.
+            if minimize_flow.is_break() {
+                stopped = Some(Stop::Interrupted);
             }
         }
-        println!("{bold}Actual checks: {info}{actual_checks}{reset}");
+
+        package_report.actual_checks = actual_checks;
+
+        // Listing skipped combos requires generating them all again, so only do so if needed.
+        if cli.wants_report() {
+            package_report.skipped = skipped_combos(&storage, package_config, &pool, &platforms);
+        }
+
+        report = Some(package_report);
+
+        // The checks may have all finished before minimizing was interrupted.
+        if flow.is_break() || stopped.is_some() {
+            let skipped = platform_combos(&storage, package_config, &pool, &platforms)
+                .count()
+                .saturating_sub(actual_checks);
+
+            if stopped.is_some() {
+                // Count the combos of this package that were not checked, then stop.
+                return Ok(PackageRun {
+                    report,
+                    stopped,
+                    unchecked_combos: skipped,
+                });
+            }
+
+            println!("{error}{bold}Skipping {skipped} remaining checks of package {name} after {package_failures} failures.{reset}");
+        }
     }
 
-    if !failures.is_empty() {
-        eprintln!("{error}{bold}Failure report:{reset}");
+    checkpoint.complete_package(&name);
+
+    if let Some(path) = checkpoint_path {
+        checkpoint.save(path)?;
+    }
+
+    println!("{bold}Actual checks: {info}{actual_checks}{reset}");
+
+    if cached_checks > 0 {
+        println!("{bold}Cached checks: {info}{cached_checks}{reset}");
+    }
+
+    if let ComboStrategy::Random { .. } = package_config.strategy() {
+        print_pair_coverage(
+            &storage,
+            package_config,
+            &pool,
+            &platforms,
+            actual_checks,
+            run.color,
+        );
+    }
+
+    Ok(PackageRun {
+        report,
+        stopped: None,
+        unchecked_combos: 0,
+    })
+}
+
+/// Prints how many pairs of features were enabled together in the random samples of a package,
+/// out of the first `actual_checks` checks.
+fn print_pair_coverage(
+    storage: &FeatureStorage,
+    package_config: Config<'_>,
+    pool: &ComboPool,
+    platforms: &[Platform],
+    actual_checks: usize,
+    color: Color,
+) {
+    let Color {
+        reset, bold, info, ..
+    } = color;
+
+    let baselines = baselines(storage, package_config);
+    let left_out = leave_one_out(storage, package_config);
+
+    // Only count the random samples that were checked, and only pairs of the features that
+    // they are made from.
+    let combos = platform_combos(storage, package_config, pool, platforms)
+        .take(actual_checks)
+        .filter(|(combo, _)| {
+            !baselines.iter().any(|(_, baseline)| baseline == combo) && !left_out.contains(combo)
+        })
+        .map(|(combo, _)| {
+            combo
+                .iter()
+                .copied()
+                .filter(|key| pool.features.contains(key))
+                .collect()
+        });
+    let (covered, total) = pair_coverage(combos, pool.features.len());
+
+    // A package with fewer than 2 features has no pairs to cover.
+    if total > 0 {
+        println!(
+            "{bold}Covered {info}{covered}{reset}{bold} of {info}{total}{reset}{bold} feature pairs ({:.1}%).{reset}",
+            covered as f64 / total as f64 * 100.0
+        );
+    }
+}
+
+/// Minimizes every failure of a package that was not already minimized, including those from
+/// before the run was resumed.
+///
+/// A subset only reproduces a failure if the same command fails with the same first error, so
+/// failures without any captured errors cannot be minimized. `outcomes` are the checks that already
+/// finished, along with their first error, which are not checked again. This returns
+/// [`ControlFlow::Break`] if minimizing was interrupted.
+fn minimize_failures<'a>(
+    run: &Run<'_>,
+    options: &CheckOptions<'_>,
+    storage: &'a FeatureStorage,
+    package_config: Config<'_>,
+    platforms: &'a [Platform],
+    outcomes: Outcomes<'a>,
+    checkpoint: &mut Checkpoint,
+) -> anyhow::Result<ControlFlow<()>> {
+    let Run {
+        cli,
+        color: Color {
+            reset, dim, info, ..
+        },
+        target_dirs,
+        checkpoint_path,
+        ..
+    } = *run;
+
+    let name = options.package;
 
-        for CheckFailure { package, features } in failures {
-            eprintln!("\t{error}Failed checking package {bold}{package}{reset} {error}with features{reset} {features:?}.");
+    let rules: Vec<Rule> = package_config
+        .rules()
+        .iter()
+        .cloned()
+        .map(|r| Rule::from_schema(r, storage))
+        .collect();
+
+    // Failures from before the run was resumed are minimized as well, if they were not
+    // already. The config may have changed since then, so skip those of old platforms.
+    let mut pending: Vec<(usize, &Platform, Vec<String>, Outcome, Diagnostic)> = Vec::new();
+
+    for (i, failure) in checkpoint.failures.iter().enumerate() {
+        if failure.package != name || failure.minimized.is_some() {
+            continue;
         }
 
-        bail!("Some packages failed to be checked.");
+        let Some(platform) = platforms.iter().find(|&p| *p == failure.platform) else {
+            continue;
+        };
+
+        let features = &failure.features;
+
+        // Without an error to compare with, any subset that fails for another reason, such as
+        // one that fails without any features, would look like the same failure.
+        let Some(primary) = failure.diagnostics.first() else {
+            println!(
+                "\t{dim}Could not minimize:{reset} {info}{features:?}{reset}{platform}{dim}, since no errors were captured.{reset}"
+            );
+            continue;
+        };
+
+        pending.push((
+            i,
+            platform,
+            features.clone(),
+            failure.outcome.clone(),
+            primary.clone(),
+        ));
     }
 
-    if cli.dry_run {
-        println!("{info}{bold}Dry run completed, no checks were run.{reset}");
+    let outcomes = Mutex::new(outcomes);
+
+    // Each failure is minimized by one worker, using that worker's target directory.
+    // Like checks, the output of each search is printed in order once it finishes.
+    run_ordered(
+        cli.jobs,
+        pending.into_iter(),
+        |worker, (_, platform, features, outcome, primary)| {
+            let platform = *platform;
+            let toolchain = platform.toolchain.as_deref();
+            let combo: Vec<FeatureKey> = features.iter().map(|f| storage.create_key(f)).collect();
+
+            let mut output = Vec::new();
+
+            let minimized = minimize::ddmin(&combo, |subset| {
+                // Subsets that are not allowed by the rules are treated as passing.
+                if is_interrupted()
+                    || !rules
+                        .iter()
+                        .all(|r| r.validate(subset) && r.allows_toolchain(subset, toolchain))
+                {
+                    return Ok(false);
+                }
+
+                let mut features: Vec<&str> = subset
+                    .iter()
+                    .map(|&key| storage.get(key).unwrap())
+                    .collect();
+
+                features.sort_unstable();
+
+                let known = outcomes
+                    .lock()
+                    .unwrap()
+                    .get(&(features.clone(), platform))
+                    .cloned();
+
+                let (subset_outcome, subset_primary) = match known {
+                    Some(known) => known,
+                    None => {
+                        output.push(format!(
+                            "\t{dim}Minimizing:{reset} {info}{features:?}{reset}{platform}"
+                        ));
+
+                        let result = check_with_features(
+                            options,
+                            platform,
+                            target_dirs[worker].as_deref(),
+                            subset,
+                            storage,
+                        )
+                        .with_context(|| format!("Tried minimizing package {name}."))?;
+
+                        let known = (result.outcome, result.diagnostics.into_iter().next());
+                        outcomes
+                            .lock()
+                            .unwrap()
+                            .insert((features, platform), known.clone());
+                        known
+                    }
+                };
+
+                // Only failures of the same command with the same first error count, since
+                // others have another cause.
+                anyhow::Ok(
+                    subset_outcome == *outcome
+                        && subset_primary.is_some_and(|subset| subset.same_error(primary)),
+                )
+            });
+
+            minimized.map(|minimized| (minimized, output))
+        },
+        |(i, platform, features, ..), result| {
+            let (minimized, output) = result?;
+
+            for line in output {
+                println!("{line}");
+            }
+
+            // An interrupted search may not be minimal, so it is repeated when resuming.
+            if is_interrupted() {
+                return Ok(ControlFlow::Break(()));
+            }
+
+            let mut minimized: Vec<String> = minimized
+                .into_iter()
+                .map(|key| storage.get(key).unwrap().to_string())
+                .collect();
+
+            minimized.sort_unstable();
+
+            if minimized.len() < features.len() {
+                println!(
+                    "\t{dim}Minimized:{reset} {info}{features:?}{reset} to {info}{minimized:?}{reset}{platform}"
+                );
+            }
+
+            checkpoint.failures[i].minimized = Some(minimized);
+
+            if let Some(path) = checkpoint_path {
+                checkpoint.save(path)?;
+            }
+
+            anyhow::Ok(ControlFlow::Continue(()))
+        },
+    )
+}
+
+/// Prints every failure of the run, with similar failures grouped together unless `verbose`.
+fn print_failure_report(failures: &[CheckFailure], verbose: bool, color: Color) {
+    let Color {
+        reset,
+        bold,
+        dim,
+        error,
+        ..
+    } = color;
+
+    if failures.is_empty() {
+        return;
+    }
+
+    eprintln!("{error}{bold}Failure report:{reset}");
+
+    let print_failure = |failure: &CheckFailure| {
+        let CheckFailure {
+            package,
+            platform,
+            features,
+            outcome,
+            diagnostics,
+            minimized,
+            baseline,
+        } = failure;
+
+        let label = match baseline {
+            Some(baseline) => format!(" {error}({baseline} baseline){reset}"),
+            None => String::new(),
+        };
+
+        match outcome {
+            Outcome::Failed { command } => eprintln!("\t{error}Failed {bold}{command}{reset} {error}on package {bold}{package}{reset} {error}with features{reset} {features:?}{platform}{label}."),
+            Outcome::TimedOut { command } => eprintln!("\t{error}Timed out running {bold}{command}{reset} {error}on package {bold}{package}{reset} {error}with features{reset} {features:?}{platform}{label}."),
+            Outcome::Passed => unreachable!("Passing checks are not failures."),
+        }
+
+        if let Some(minimized) = minimized.as_ref().filter(|m| m.len() < features.len()) {
+            eprintln!("\t\t{error}Smallest failing subset:{reset} {minimized:?}");
+        }
+
+        for diagnostic in diagnostics {
+            eprintln!("\t\t{dim}{diagnostic}{reset}");
+        }
+    };
+
+    // A failing baseline often explains many other failures, so they are listed first.
+    let (baseline_failures, other_failures): (Vec<&CheckFailure>, Vec<&CheckFailure>) =
+        failures.iter().partition(|f| f.baseline.is_some());
+
+    if !baseline_failures.is_empty() {
+        eprintln!("\t{error}{bold}Baseline checks failed, which may cause many of the other failures. Consider fixing them first:{reset}");
+        baseline_failures.into_iter().for_each(print_failure);
+    }
+
+    if verbose {
+        other_failures.iter().copied().for_each(print_failure);
     } else {
-        println!("{success}{bold}Feature combination checks successful! Congrats :){reset}");
+        let clusters = cluster_failures(other_failures.iter().copied());
+
+        for cluster in &clusters {
+            let [first, ref rest @ ..] = cluster.failures[..] else {
+                unreachable!("Clusters are never empty.");
+            };
+
+            if rest.is_empty() {
+                print_failure(first);
+                continue;
+            }
+
+            let package = &first.package;
+            let count = cluster.failures.len();
+
+            let containing = match cluster.common_features[..] {
+                [] => String::new(),
+                ref common => format!(" {error}containing{reset} {common:?}"),
+            };
+
+            // Only show the platform if all failures share it.
+            let platform = if rest.iter().all(|f| f.platform == first.platform) {
+                first.platform.to_string()
+            } else {
+                String::new()
+            };
+
+            match first.outcome {
+                Outcome::Failed { ref command } => eprintln!("\t{error}Failed {bold}{command}{reset} {error}on package {bold}{package}{reset} {error}with {bold}{count}{reset} {error}combinations{reset}{containing}{platform}."),
+                Outcome::TimedOut { ref command } => eprintln!("\t{error}Timed out running {bold}{command}{reset} {error}on package {bold}{package}{reset} {error}with {bold}{count}{reset} {error}combinations{reset}{containing}{platform}."),
+                Outcome::Passed => unreachable!("Passing checks are not failures."),
+            }
+
+            let mut minimized: Vec<&Vec<String>> = cluster
+                .failures
+                .iter()
+                .filter_map(|f| f.minimized.as_ref())
+                .collect();
+
+            minimized.sort_unstable();
+            minimized.dedup();
+
+            for minimized in minimized {
+                eprintln!("\t\t{error}Smallest failing subset:{reset} {minimized:?}");
+            }
+
+            if let Some(diagnostic) = cluster.primary_diagnostic() {
+                eprintln!("\t\t{dim}{diagnostic}{reset}");
+            }
+        }
+
+        if clusters.len() < other_failures.len() {
+            eprintln!(
+                "{dim}Similar failures were grouped together. Pass `--verbose` to list all {} of them.{reset}",
+                other_failures.len(),
+            );
+        }
     }
+}
 
-    Ok(())
+/// Prints the rules suggested for each package, for `--suggest-rules`.
+fn print_suggested_rules(package_reports: &[PackageReport], color: Color) {
+    let Color {
+        reset, bold, info, ..
+    } = color;
+
+    for package_report in package_reports {
+        let (suggestions, unexplained) = suggest::suggest_rules(&package_report.checks);
+        let name = &package_report.name;
+
+        if !suggestions.is_empty() {
+            println!("{bold}Suggested rules for package {info}{name}{reset}{bold}, to add to {info}{name}.toml{reset}{bold} in the config folder:{reset}");
+
+            for suggestion in suggestions {
+                println!("\n{suggestion}");
+            }
+        }
+
+        if unexplained > 0 {
+            println!("{bold}{info}{unexplained}{reset}{bold} failures of package {info}{name}{reset}{bold} are not explained by any suggested rule.{reset}");
+        }
+    }
+}
+
+/// Everything shared between the checks of every package in a run.
+#[derive(Clone, Copy)]
+struct Run<'a> {
+    cli: &'a CLI,
+    config: &'a WorkspaceConfig,
+    color: Color,
+    /// The target directory of each worker, if there are multiple.
+    target_dirs: &'a [Option<PathBuf>],
+    /// The cache folder and the folders that affect each package, if caching is enabled.
+    cache: Option<(&'a Path, &'a HashMap<String, Vec<PathBuf>>)>,
+    workspace_root: &'a Path,
+    checkpoint_path: Option<&'a Path>,
+    progress: Option<&'a Progress>,
+}
+
+/// The result of [`check_package()`].
+struct PackageRun {
+    /// The report of the package, unless this is a dry run.
+    report: Option<PackageReport>,
+    /// Set if checking stopped before all packages were checked.
+    stopped: Option<Stop>,
+    /// The checks of the package that were never run, because checking stopped.
+    unchecked_combos: usize,
+}
+
+/// The reason checking stopped before all packages were checked.
+enum Stop {
+    /// Ctrl-C was pressed.
+    Interrupted,
+    /// The amount of failures reached `--max-failures`.
+    MaxFailures,
+}
+
+/// The outcome and first error of every check that finished, by its sorted features and platform.
+type Outcomes<'a> = HashMap<(Vec<&'a str>, &'a Platform), (Outcome, Option<Diagnostic>)>;
+
+/// A single feature combination to check on a single platform.
+struct Check<'a> {
+    combo: Box<[FeatureKey]>,
+    /// The names of the features in [`Self::combo`], sorted.
+    features: Vec<&'a str>,
+    platform: &'a Platform,
+    /// The cache for [`Self::platform`], if caching is enabled.
+    cache: Option<&'a PackageCache>,
+    /// True if this check passed in a previous run, so it does not need to be run again.
+    cached: bool,
+    /// The baseline this check is, if any.
+    baseline: Option<Baseline>,
+}
+
+/// Returns every pair of toolchain and target that a package is checked on.
+///
+/// An empty list of toolchains or targets is treated as only the default one.
+fn platforms(package_config: Config<'_>) -> Vec<Platform> {
+    fn or_default(values: &[String]) -> Vec<Option<String>> {
+        if values.is_empty() {
+            vec![None]
+        } else {
+            values.iter().cloned().map(Some).collect()
+        }
+    }
+
+    let targets = or_default(package_config.targets());
+
+    or_default(package_config.toolchains())
+        .into_iter()
+        .flat_map(|toolchain| {
+            targets.iter().map(move |target| Platform {
+                toolchain: toolchain.clone(),
+                target: target.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Returns every combo of a package that is skipped, either entirely or on a certain platform.
+fn skipped_combos(
+    storage: &FeatureStorage,
+    package_config: Config<'_>,
+    pool: &ComboPool,
+    platforms: &[Platform],
+) -> Vec<SkippedCombo> {
+    all_combos(storage, package_config, pool, platforms)
+        .filter_map(|combo| {
+            let PlatformCombo {
+                combo,
+                platform,
+                skipped: Some(reason),
+            } = combo
+            else {
+                return None;
+            };
+
+            let mut features: Vec<String> = combo
+                .iter()
+                .map(|&key| storage.get(key).unwrap().to_string())
+                .collect();
+
+            features.sort_unstable();
+
+            Some(SkippedCombo {
+                features,
+                platform: platform.map_or_else(Platform::default, |i| platforms[i].clone()),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Returns the amount of checks required for a package, counting each platform of a combo
+/// separately.
+fn count_combos(package: &Package, config: &WorkspaceConfig) -> usize {
+    let package_config = config.get(&package.name);
+    let storage = intern_features(package.features.clone(), package_config);
+    let pool = ComboPool::new(&storage, package_config);
+
+    platform_combos(&storage, package_config, &pool, &platforms(package_config)).count()
 }
 
 /// Processes the packages in a [`Manifest`] and returns them in a [`Vec`].
@@ -161,7 +1324,20 @@ fn process_packages(
     Ok(packages)
 }
 
+#[derive(Serialize, Deserialize, Debug)]
 struct CheckFailure {
     pub package: String,
+    #[serde(flatten)]
+    pub platform: Platform,
     pub features: Vec<String>,
+    /// How the check failed. This is never [`Outcome::Passed`].
+    pub outcome: Outcome,
+    /// The first few errors emitted by the compiler, limited by `--max-diagnostics`.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The smallest subset of [`Self::features`] that fails the same way, found by `--minimize`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimized: Option<Vec<String>>,
+    /// The baseline this check is, if it is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<Baseline>,
 }
@@ -1,5 +1,5 @@
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 /// A subset of the metadata returned by `cargo-metadata` that's required for `flag-frenzy`.
 #[derive(Deserialize, Debug)]
@@ -9,6 +9,10 @@ pub struct Manifest {
     /// If [`load_manifest()`](super::load_manifest) is used, this will not contain any external
     /// dependencies.
     pub packages: Vec<Package>,
+    /// The directory where Cargo places build artifacts, usually `target`.
+    pub target_directory: PathBuf,
+    /// The directory containing the workspace's root `Cargo.toml` and `Cargo.lock`.
+    pub workspace_root: PathBuf,
 }
 
 /// Represents a single package.
@@ -18,4 +22,31 @@ pub struct Package {
     pub name: String,
     /// A list of all features in a crate.
     pub features: HashMap<String, Vec<String>>,
+    /// The path to this crate's `Cargo.toml`.
+    pub manifest_path: PathBuf,
+    /// A list of all dependencies of this crate.
+    pub dependencies: Vec<Dependency>,
+    /// A list of all targets of this crate, such as its library, tests, and examples.
+    pub targets: Vec<Target>,
+}
+
+/// Represents a dependency of a [`Package`].
+#[derive(Deserialize, Debug)]
+pub struct Dependency {
+    /// The directory of the dependency, if it is a path dependency.
+    pub path: Option<PathBuf>,
+}
+
+/// Represents a single target of a [`Package`], such as its library or an example.
+#[derive(Deserialize, Debug)]
+pub struct Target {
+    /// The name of the target.
+    pub name: String,
+    /// The kinds of the target, such as `lib`, `bin`, `example`, `test`, or `bench`.
+    ///
+    /// Libraries may have multiple kinds, such as `rlib` and `cdylib`.
+    pub kind: Vec<String>,
+    /// The features that must be enabled for this target to be built.
+    #[serde(default, rename = "required-features")]
+    pub required_features: Vec<String>,
 }
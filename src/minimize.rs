@@ -0,0 +1,122 @@
+//! Shrinking failing feature combinations to a smaller subset that still fails.
+
+/// Returns a subset of `input` that still fails, using the
+/// [delta debugging](https://www.st.cs.uni-saarland.de/papers/tse2002/) algorithm.
+///
+/// `input` itself is assumed to fail. The result is 1-minimal: removing any single element from
+/// it would make it pass, according to `fails`. Elements keep their original order.
+///
+/// `fails` may be called many times, so it should cache results if they are expensive.
+pub fn ddmin<T: Clone, E>(
+    input: &[T],
+    mut fails: impl FnMut(&[T]) -> Result<bool, E>,
+) -> Result<Vec<T>, E> {
+    // Delta debugging assumes the empty set passes, so check that first.
+    if input.is_empty() || fails(&[])? {
+        return Ok(Vec::new());
+    }
+
+    let mut current = input.to_vec();
+    let mut granularity = 2;
+
+    while current.len() >= 2 {
+        let chunks = split(&current, granularity);
+        let mut reduced = false;
+
+        // Try to find a single chunk that fails by itself.
+        for chunk in &chunks {
+            if fails(chunk)? {
+                current = chunk.clone();
+                granularity = 2;
+                reduced = true;
+                break;
+            }
+        }
+
+        // Try to remove a single chunk. This is unnecessary with 2 chunks, since each complement
+        // is just the other chunk, which was already tested.
+        if !reduced && granularity > 2 {
+            for i in 0..chunks.len() {
+                let complement: Vec<T> = chunks
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .flat_map(|(_, chunk)| chunk.iter().cloned())
+                    .collect();
+
+                if fails(&complement)? {
+                    current = complement;
+                    granularity = (granularity - 1).max(2);
+                    reduced = true;
+                    break;
+                }
+            }
+        }
+
+        if !reduced {
+            // Every chunk is a single element, so removing any of them makes it pass.
+            if granularity >= current.len() {
+                break;
+            }
+
+            granularity = (granularity * 2).min(current.len());
+        }
+    }
+
+    Ok(current)
+}
+
+/// Splits `items` into `n` chunks of roughly equal size.
+fn split<T: Clone>(items: &[T], n: usize) -> Vec<Vec<T>> {
+    let mut chunks = Vec::with_capacity(n);
+    let mut start = 0;
+
+    for i in 0..n {
+        // Spread the remainder over the first chunks.
+        let end = start + items.len() / n + usize::from(i < items.len() % n);
+        chunks.push(items[start..end].to_vec());
+        start = end;
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn minimize(input: &[u32], fails: impl Fn(&[u32]) -> bool) -> Vec<u32> {
+        ddmin::<_, Infallible>(input, |subset| Ok(fails(subset))).unwrap()
+    }
+
+    #[test]
+    fn single_culprit() {
+        let result = minimize(&[1, 2, 3, 4, 5], |s| s.contains(&3));
+        assert_eq!(result, [3]);
+    }
+
+    #[test]
+    fn interacting_pair() {
+        let result = minimize(&[1, 2, 3, 4, 5, 6, 7], |s| s.contains(&2) && s.contains(&6));
+        assert_eq!(result, [2, 6]);
+    }
+
+    #[test]
+    fn already_minimal() {
+        let result = minimize(&[1, 2, 3], |s| s.len() == 3);
+        assert_eq!(result, [1, 2, 3]);
+    }
+
+    #[test]
+    fn empty_fails() {
+        let result = minimize(&[1, 2], |_| true);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn split_evenly() {
+        assert_eq!(split(&[1, 2, 3, 4, 5], 2), [vec![1, 2, 3], vec![4, 5]]);
+        assert_eq!(split(&[1, 2, 3], 3), [vec![1], vec![2], vec![3]]);
+    }
+}
@@ -0,0 +1,226 @@
+//! A small worker pool that runs jobs in parallel but reports their results in order.
+
+use std::{
+    collections::BTreeMap,
+    ops::ControlFlow,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+/// Runs `work` on each item yielded by `items` using `jobs` worker threads.
+///
+/// `work` is passed the index of the worker that is running it, which is always in the range
+/// `0..jobs`. This can be used to give each worker its own resources, such as a target directory.
+///
+/// `on_result` is called on the current thread with each item and its result. Even though items
+/// may finish in any order, `on_result` will always be called in the same order that `items`
+/// yielded them. If `on_result` returns [`ControlFlow::Break`] or an [`Err`], no new items will be
+/// started, the results of items that are still running will be discarded, and this will return
+/// once they have finished.
+///
+/// `items` is consumed lazily, so at most `jobs` items will be pulled from it ahead of time.
+///
+/// # Panics
+///
+/// If `jobs` is 0.
+pub fn run_ordered<T, R, E>(
+    jobs: usize,
+    items: impl Iterator<Item = T>,
+    work: impl Fn(usize, &T) -> R + Sync,
+    mut on_result: impl FnMut(T, R) -> Result<ControlFlow<()>, E>,
+) -> Result<ControlFlow<()>, E>
+where
+    T: Send,
+    R: Send,
+{
+    assert!(jobs > 0, "Cannot run jobs with a pool of 0 workers.");
+
+    // There's no need to spawn any threads if there's only one worker.
+    if jobs == 1 {
+        for item in items {
+            let result = work(0, &item);
+
+            if on_result(item, result)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        return Ok(ControlFlow::Continue(()));
+    }
+
+    let (job_sender, job_receiver) = mpsc::channel::<(usize, T)>();
+    let (result_sender, result_receiver) = mpsc::channel::<(usize, T, R)>();
+
+    // All workers share the same job queue, so it must be behind a lock.
+    let job_receiver = Mutex::new(job_receiver);
+
+    let work = &work;
+    let job_receiver = &job_receiver;
+
+    thread::scope(move |s| {
+        for worker in 0..jobs {
+            let result_sender = result_sender.clone();
+
+            s.spawn(move || loop {
+                // The lock is released as soon as a job is received, not once it is finished.
+                let Ok((i, item)) = job_receiver.lock().unwrap().recv() else {
+                    // The sender was dropped, so there are no jobs left.
+                    break;
+                };
+
+                let result = work(worker, &item);
+
+                if result_sender.send((i, item, result)).is_err() {
+                    // The receiver was dropped, so nobody cares about the result anymore.
+                    break;
+                }
+            });
+        }
+
+        // Drop the original sender so that the channel closes once all workers exit.
+        drop(result_sender);
+
+        let mut items = items.enumerate();
+        let mut in_flight = 0;
+
+        // Results that finished before the results ahead of them, keyed by their index.
+        let mut finished = BTreeMap::new();
+        let mut next = 0;
+
+        loop {
+            // Keep every worker busy, without pulling more items than necessary.
+            while in_flight < jobs {
+                let Some(job) = items.next() else {
+                    break;
+                };
+
+                job_sender.send(job).unwrap();
+                in_flight += 1;
+            }
+
+            if in_flight == 0 {
+                break;
+            }
+
+            let (i, item, result) = result_receiver
+                .recv()
+                .expect("All workers exited while jobs were still running.");
+
+            in_flight -= 1;
+            finished.insert(i, (item, result));
+
+            // Report all results that are now in order.
+            while let Some((item, result)) = finished.remove(&next) {
+                next += 1;
+
+                if on_result(item, result)?.is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread::sleep, time::Duration};
+
+    #[test]
+    fn results_are_ordered() {
+        let mut results = Vec::new();
+
+        let flow = run_ordered(
+            4,
+            0..20_u64,
+            // Make earlier items take longer, so they finish out of order.
+            |_, &i| sleep(Duration::from_millis(20 - i)),
+            |i, ()| {
+                results.push(i);
+                Ok::<_, ()>(ControlFlow::Continue(()))
+            },
+        )
+        .unwrap();
+
+        assert!(flow.is_continue());
+        assert_eq!(results, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn worker_indices() {
+        let mut workers = Vec::new();
+
+        let flow = run_ordered(
+            3,
+            0..30,
+            |worker, _| worker,
+            |_, worker| {
+                workers.push(worker);
+                Ok::<_, ()>(ControlFlow::Continue(()))
+            },
+        )
+        .unwrap();
+
+        assert!(flow.is_continue());
+        assert!(workers.iter().all(|&worker| worker < 3));
+    }
+
+    #[test]
+    fn error_stops_pool() {
+        let mut seen = 0;
+
+        let result = run_ordered(
+            2,
+            0..100,
+            |_, &i| i,
+            |_, i| {
+                seen += 1;
+
+                if i == 5 {
+                    Err(i)
+                } else {
+                    Ok(ControlFlow::Continue(()))
+                }
+            },
+        );
+
+        assert_eq!(result, Err(5));
+        assert_eq!(seen, 6);
+    }
+
+    #[test]
+    fn break_stops_pool() {
+        let mut seen = 0;
+
+        let result = run_ordered(
+            2,
+            0..100,
+            |_, &i| i,
+            |_, i| {
+                seen += 1;
+
+                if i == 5 {
+                    Ok::<_, ()>(ControlFlow::Break(()))
+                } else {
+                    Ok(ControlFlow::Continue(()))
+                }
+            },
+        );
+
+        assert_eq!(result, Ok(ControlFlow::Break(())));
+        assert_eq!(seen, 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_jobs() {
+        let _ = run_ordered(
+            0,
+            0..1,
+            |_, _| (),
+            |_, ()| Ok::<_, ()>(ControlFlow::Continue(())),
+        );
+    }
+}
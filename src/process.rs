@@ -0,0 +1,176 @@
+//! Running child processes with a deadline.
+
+use std::{
+    io::{self, Read},
+    process::{Child, Command, Output, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How often a running process is checked to see if it has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The [`Output`] of a process that may have been killed.
+pub struct TimedOutput {
+    /// The output of the process. If it timed out, this contains everything it printed before it
+    /// was killed.
+    pub output: Output,
+    /// True if the process did not exit before its deadline, and was killed.
+    pub timed_out: bool,
+}
+
+/// Equivalent to [`Command::output()`], but kills the process if it is still running at
+/// `deadline`.
+///
+/// The process is started in its own process group (or job, on Windows), so that any processes it
+/// spawns, such as `rustc` or build scripts, are killed along with it. A side effect of this is
+/// that the process will not receive signals sent to the terminal, such as Ctrl-C.
+///
+/// If `deadline` is [`None`], this just calls [`Command::output()`].
+pub fn output_with_deadline(
+    command: &mut Command,
+    deadline: Option<Instant>,
+) -> io::Result<TimedOutput> {
+    let Some(deadline) = deadline else {
+        return command.output().map(|output| TimedOutput {
+            output,
+            timed_out: false,
+        });
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    new_process_group(command);
+
+    let mut child = command.spawn()?;
+
+    // The pipes must be drained while waiting, else the process may block once they are full.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let mut timed_out = false;
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            kill_tree(&mut child)?;
+            timed_out = true;
+
+            break child.wait()?;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(TimedOutput {
+        output: Output {
+            status,
+            stdout: stdout.join().unwrap(),
+            stderr: stderr.join().unwrap(),
+        },
+        timed_out,
+    })
+}
+
+/// Reads a pipe to the end on a new thread, returning its contents when joined.
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+
+        if let Some(mut pipe) = pipe {
+            // Errors are ignored, since whatever was read up until then is still useful.
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+
+        buffer
+    })
+}
+
+/// Configures a [`Command`] to start in a new process group, so [`kill_tree()`] can find all of
+/// its descendants.
+#[cfg(unix)]
+fn new_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn new_process_group(_command: &mut Command) {}
+
+/// Kills a [`Child`] and all processes in its process group.
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    // A negative PID signals the entire process group, which was created by `new_process_group()`.
+    let status = Command::new("kill")
+        .args(["-s", "KILL", "--", &format!("-{}", child.id())])
+        .stderr(Stdio::null())
+        .status();
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        // Fall back to just killing the direct child.
+        _ => child.kill(),
+    }
+}
+
+/// Kills a [`Child`] and all processes it spawned.
+#[cfg(windows)]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    let status = Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        // Fall back to just killing the direct child.
+        _ => child.kill(),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finishes_before_deadline() {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let result =
+            output_with_deadline(Command::new("echo").arg("hello"), Some(deadline)).unwrap();
+
+        assert!(!result.timed_out);
+        assert!(result.output.status.success());
+        assert_eq!(result.output.stdout, b"hello\n");
+    }
+
+    #[test]
+    fn kills_process_tree() {
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(200);
+
+        // The shell spawns a child `sleep`, which must also be killed for the pipes to close.
+        let result = output_with_deadline(
+            Command::new("sh").args(["-c", "echo started; sleep 30; echo finished"]),
+            Some(deadline),
+        )
+        .unwrap();
+
+        assert!(result.timed_out);
+        assert!(!result.output.status.success());
+        assert_eq!(result.output.stdout, b"started\n");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,294 @@
+//! Showing how far along a run is, and how long it will take to finish.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    io::{self, IsTerminal, Write},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// How many of the most recent check durations are used to estimate the time remaining.
+const ROLLING_WINDOW: usize = 20;
+
+/// How often a progress line is printed when stdout is not a terminal.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The longest the list of running combos may be before it is cut off, so that the status line
+/// fits on a single line of the terminal.
+const MAX_RUNNING_LEN: usize = 50;
+
+/// Tracks the progress of a run and displays it.
+///
+/// If stdout is a terminal, a status line is kept at the bottom of the output and redrawn whenever
+/// a check starts. Otherwise, a plain progress line is printed every [`PLAIN_INTERVAL`].
+///
+/// This may be shared between threads, so that workers can report which combos they are checking.
+pub struct Progress {
+    state: Mutex<State>,
+    is_terminal: bool,
+    /// The amount of checks that run at once, which divides the time remaining.
+    jobs: usize,
+}
+
+struct State {
+    started: Instant,
+    /// When the last plain progress line was printed.
+    last_printed: Instant,
+    /// Set if the status line is currently shown, so that it must be cleared before printing.
+    drawn: bool,
+    package: String,
+    package_checked: usize,
+    package_total: usize,
+    checked: usize,
+    total: usize,
+    /// The durations of the most recent checks that were not cached.
+    durations: VecDeque<Duration>,
+    /// The combos that are currently being checked, in the order they were started.
+    running: Vec<String>,
+}
+
+impl Progress {
+    /// Creates a new progress display for a run of `total` checks, with `jobs` checks at once.
+    pub fn new(total: usize, jobs: usize) -> Self {
+        let now = Instant::now();
+
+        Self {
+            state: Mutex::new(State {
+                started: now,
+                last_printed: now,
+                drawn: false,
+                package: String::new(),
+                package_checked: 0,
+                package_total: 0,
+                checked: 0,
+                total,
+                durations: VecDeque::with_capacity(ROLLING_WINDOW),
+                running: Vec::new(),
+            }),
+            is_terminal: io::stdout().is_terminal(),
+            jobs,
+        }
+    }
+
+    /// Starts tracking a package, of which `checked` out of `total` checks were already completed
+    /// before the run was resumed.
+    pub fn start_package(&self, name: &str, checked: usize, total: usize) {
+        let mut state = self.lock();
+
+        state.package = name.to_string();
+        state.package_checked = checked;
+        state.package_total = total;
+    }
+
+    /// Stops tracking the current package and clears the status line.
+    ///
+    /// Any checks of the package that were not completed, such as due to
+    /// `--max-package-failures`, are no longer counted towards the total.
+    pub fn finish_package(&self) {
+        let mut state = self.lock();
+
+        state.clear();
+        state.total = state
+            .total
+            .saturating_sub(state.package_total.saturating_sub(state.package_checked));
+        state.package_total = state.package_checked;
+        state.running.clear();
+    }
+
+    /// Marks a combo as being checked, and redraws the status line with it.
+    pub fn start_check(&self, combo: impl Display) {
+        let mut state = self.lock();
+
+        state.running.push(combo.to_string());
+
+        if self.is_terminal {
+            state.clear();
+            state.draw(self.jobs);
+        }
+    }
+
+    /// Clears the status line so that other output can be printed, until the returned
+    /// [`Suspended`] is dropped.
+    ///
+    /// Checks cannot start while the status line is suspended.
+    pub fn suspend(&self) -> Suspended<'_> {
+        let mut state = self.lock();
+        state.clear();
+
+        Suspended {
+            state,
+            progress: self,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Progress is only for display, so a panic while drawing should not stop the run.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Holds the status line back while other output is printed. See [`Progress::suspend()`].
+pub struct Suspended<'a> {
+    state: MutexGuard<'a, State>,
+    progress: &'a Progress,
+}
+
+impl Suspended<'_> {
+    /// Marks a combo as checked, along with how long it took if it was not cached.
+    pub fn finish_check(&mut self, combo: impl Display, duration: Option<Duration>) {
+        let state = &mut *self.state;
+        let combo = combo.to_string();
+
+        if let Some(i) = state.running.iter().position(|c| *c == combo) {
+            state.running.remove(i);
+        }
+
+        state.package_checked += 1;
+        state.checked += 1;
+
+        if let Some(duration) = duration {
+            if state.durations.len() == ROLLING_WINDOW {
+                state.durations.pop_front();
+            }
+
+            state.durations.push_back(duration);
+        }
+
+        if !self.progress.is_terminal && state.last_printed.elapsed() >= PLAIN_INTERVAL {
+            println!("{}", state.line(self.progress.jobs));
+            state.last_printed = Instant::now();
+        }
+    }
+}
+
+impl Drop for Suspended<'_> {
+    fn drop(&mut self) {
+        if self.progress.is_terminal {
+            self.state.draw(self.progress.jobs);
+        }
+    }
+}
+
+impl State {
+    /// Draws the status line, without a trailing newline so that it can be cleared later.
+    fn draw(&mut self, jobs: usize) {
+        print!("{}", self.line(jobs));
+        let _ = io::stdout().flush();
+
+        self.drawn = true;
+    }
+
+    /// Clears the status line, if it is drawn.
+    fn clear(&mut self) {
+        if self.drawn {
+            // Move to the start of the line and erase it.
+            print!("\r\x1b[2K");
+            let _ = io::stdout().flush();
+
+            self.drawn = false;
+        }
+    }
+
+    fn line(&self, jobs: usize) -> String {
+        let mut line = format!(
+            "Progress: {}/{} checks of {}, {}/{} overall, {} elapsed, ETA {}",
+            self.package_checked,
+            self.package_total,
+            self.package,
+            self.checked,
+            self.total,
+            FormatDuration(self.started.elapsed()),
+            self.eta(jobs).map_or_else(
+                || "unknown".to_string(),
+                |eta| FormatDuration(eta).to_string()
+            ),
+        );
+
+        if let Some((first, rest)) = self.running.split_first() {
+            let mut current: String = first.chars().take(MAX_RUNNING_LEN).collect();
+
+            if current.len() < first.len() {
+                current.push_str("...");
+            }
+
+            line.push_str(&format!(", checking {current}"));
+
+            if !rest.is_empty() {
+                line.push_str(&format!(" and {} more", rest.len()));
+            }
+        }
+
+        line
+    }
+
+    /// Estimates the time remaining from the average duration of recent checks.
+    fn eta(&self, jobs: usize) -> Option<Duration> {
+        if self.durations.is_empty() {
+            return None;
+        }
+
+        let average = self.durations.iter().sum::<Duration>() / self.durations.len() as u32;
+        let remaining = self.total.saturating_sub(self.checked);
+
+        Some(average * remaining as u32 / jobs as u32)
+    }
+}
+
+/// Formats a [`Duration`] as hours, minutes, and seconds, such as `1h 02m 03s`.
+struct FormatDuration(Duration);
+
+impl Display for FormatDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+        if hours > 0 {
+            write!(f, "{hours}h {minutes:02}m {secs:02}s")
+        } else if minutes > 0 {
+            write!(f, "{minutes}m {secs:02}s")
+        } else {
+            write!(f, "{secs}s")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration() {
+        let format = |secs| FormatDuration(Duration::from_secs(secs)).to_string();
+
+        assert_eq!(format(4), "4s");
+        assert_eq!(format(123), "2m 03s");
+        assert_eq!(format(3723), "1h 02m 03s");
+    }
+
+    #[test]
+    fn rolling_eta() {
+        let progress = Progress::new(100, 2);
+
+        {
+            let mut suspended = progress.suspend();
+
+            // Only the most recent checks are averaged.
+            for _ in 0..ROLLING_WINDOW {
+                suspended.finish_check("[]", Some(Duration::from_secs(60)));
+            }
+
+            for _ in 0..ROLLING_WINDOW {
+                suspended.finish_check("[]", Some(Duration::from_secs(2)));
+            }
+
+            // Cached checks count as checked, but do not affect the average.
+            suspended.finish_check("[]", None);
+        }
+
+        let state = progress.lock();
+
+        assert_eq!(state.checked, 41);
+        assert_eq!(state.eta(2), Some(Duration::from_secs(59)));
+    }
+}
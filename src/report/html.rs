@@ -0,0 +1,398 @@
+//! Writing a [`Report`] as a single, self-contained HTML page.
+//!
+//! Each package gets a summary, a matrix showing how each pair of features fared, and the details
+//! of every failure. The page has no scripts or external resources, so it can be viewed offline.
+
+use super::{escape, PackageReport, Report};
+use crate::{runner::Outcome, CheckFailure};
+use anyhow::Context;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// The styles of the page, including the colors of each [`PairStatus`].
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table.summary td { padding: 0.2em 1em 0.2em 0; }
+table.matrix { border-collapse: collapse; font-size: 0.8em; }
+table.matrix th, table.matrix td { border: 1px solid #ccc; }
+table.matrix td { width: 1.2em; height: 1.2em; }
+table.matrix th.column { writing-mode: vertical-rl; transform: rotate(180deg); white-space: nowrap; }
+table.matrix th.row { text-align: right; white-space: nowrap; }
+.passed { background: #4caf50; }
+.failed { background: #e53935; }
+.skipped { background: #bdbdbd; }
+.untested { background: #fff; }
+.legend span { display: inline-block; padding: 0.2em 0.6em; margin-right: 0.5em; border: 1px solid #ccc; }
+details { margin: 0.3em 0; }
+pre { background: #f5f5f5; padding: 0.5em; overflow-x: auto; }
+";
+
+/// Writes a [`Report`] to a file as HTML.
+pub fn write(report: &Report<'_>, path: &Path) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {path:?}."))?;
+    let mut writer = BufWriter::new(file);
+
+    write_html(report, &mut writer)
+        .and_then(|()| writer.flush())
+        .with_context(|| format!("Failed to write HTML report to {path:?}."))
+}
+
+fn write_html(report: &Report<'_>, w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, r#"<html lang="en">"#)?;
+    writeln!(
+        w,
+        r#"<head><meta charset="utf-8"><title>flag-frenzy report</title>"#
+    )?;
+    writeln!(w, "<style>{STYLE}</style></head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>flag-frenzy report</h1>")?;
+
+    writeln!(
+        w,
+        "<p>{} packages checked, {} failures, {} checks left unchecked.</p>",
+        report.packages.len(),
+        report.failures.len(),
+        report.unchecked_checks,
+    )?;
+
+    writeln!(w, r#"<p class="legend">"#)?;
+
+    for status in [
+        PairStatus::Passed,
+        PairStatus::Failed,
+        PairStatus::Skipped,
+        PairStatus::Untested,
+    ] {
+        let class = status.class();
+        writeln!(w, r#"<span class="{class}">{class}</span>"#)?;
+    }
+
+    writeln!(w, "</p>")?;
+
+    for package in &report.packages {
+        let failures: Vec<&CheckFailure> = report
+            .failures
+            .iter()
+            .filter(|f| f.package == package.name)
+            .collect();
+
+        write_package(package, &failures, w)?;
+    }
+
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+fn write_package(
+    package: &PackageReport,
+    failures: &[&CheckFailure],
+    w: &mut impl Write,
+) -> io::Result<()> {
+    let passed = package
+        .checks
+        .iter()
+        .filter(|c| c.outcome == Outcome::Passed)
+        .count();
+    let cached = package.checks.iter().filter(|c| c.cached).count();
+
+    writeln!(w, "<h2>{}</h2>", escape(&package.name))?;
+    writeln!(w, r#"<table class="summary">"#)?;
+
+    for (label, value) in [
+        ("Features", package.features.len()),
+        ("Checks", package.checks.len()),
+        ("Passed", passed),
+        ("Cached", cached),
+        ("Failed", package.checks.len() - passed),
+        ("Skipped", package.skipped.len()),
+    ] {
+        writeln!(w, "<tr><td>{label}</td><td>{value}</td></tr>")?;
+    }
+
+    writeln!(w, "</table>")?;
+
+    write_matrix(package, w)?;
+
+    if !failures.is_empty() {
+        writeln!(w, "<h3>Failures</h3>")?;
+
+        for failure in failures {
+            let (verb, command) = match &failure.outcome {
+                Outcome::Failed { command } => ("Failed", command),
+                Outcome::TimedOut { command } => ("Timed out running", command),
+                Outcome::Passed => unreachable!("Passing checks are not failures."),
+            };
+
+            writeln!(
+                w,
+                "<details><summary>{verb} <code>{}</code> with features <code>[{}]</code>{}{}</summary>",
+                escape(command),
+                escape(failure.features.join(", ")),
+                escape(&failure.platform),
+                match failure.baseline {
+                    Some(baseline) => format!(" <strong>({baseline} baseline)</strong>"),
+                    None => String::new(),
+                },
+            )?;
+
+            if let Some(minimized) = failure
+                .minimized
+                .as_ref()
+                .filter(|m| m.len() < failure.features.len())
+            {
+                writeln!(
+                    w,
+                    "<p>Smallest failing subset: <code>[{}]</code></p>",
+                    escape(minimized.join(", ")),
+                )?;
+            }
+
+            if !failure.diagnostics.is_empty() {
+                writeln!(w, "<pre>")?;
+
+                for diagnostic in &failure.diagnostics {
+                    writeln!(w, "{}", escape(diagnostic))?;
+                }
+
+                writeln!(w, "</pre>")?;
+            }
+
+            writeln!(w, "</details>")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a table where each cell shows how the checks containing a pair of features fared.
+///
+/// Cells on the diagonal represent a single feature.
+fn write_matrix(package: &PackageReport, w: &mut impl Write) -> io::Result<()> {
+    if package.features.is_empty() {
+        return Ok(());
+    }
+
+    let matrix = PairMatrix::new(package);
+
+    writeln!(w, "<h3>Feature matrix</h3>")?;
+    writeln!(w, r#"<table class="matrix">"#)?;
+    write!(w, "<tr><th></th>")?;
+
+    for feature in &package.features {
+        write!(w, r#"<th class="column">{}</th>"#, escape(feature))?;
+    }
+
+    writeln!(w, "</tr>")?;
+
+    for (i, row) in package.features.iter().enumerate() {
+        write!(w, r#"<tr><th class="row">{}</th>"#, escape(row))?;
+
+        for (j, column) in package.features.iter().enumerate() {
+            let counts = matrix.get(i, j);
+            let status = counts.status();
+
+            let title = if i == j {
+                escape(row)
+            } else {
+                escape(format!("{row} + {column}"))
+            };
+
+            write!(
+                w,
+                r#"<td class="{}" title="{title}: {} passed, {} failed, {} skipped"></td>"#,
+                status.class(),
+                counts.passed,
+                counts.failed,
+                counts.skipped,
+            )?;
+        }
+
+        writeln!(w, "</tr>")?;
+    }
+
+    writeln!(w, "</table>")
+}
+
+/// How many checks containing a pair of features passed, failed, or were skipped.
+#[derive(Clone, Copy, Default, Debug)]
+struct PairCounts {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    /// Whether the combination of exactly this pair, or this feature on the diagonal, passed on
+    /// every platform, if it was checked.
+    exact_passed: Option<bool>,
+}
+
+impl PairCounts {
+    /// Returns the status of the exact combination if it was checked, since failures of larger
+    /// combinations may be caused by other features. Otherwise, all checks containing the pair
+    /// are considered.
+    fn status(&self) -> PairStatus {
+        if let Some(passed) = self.exact_passed {
+            return if passed {
+                PairStatus::Passed
+            } else {
+                PairStatus::Failed
+            };
+        }
+
+        if self.failed > 0 {
+            PairStatus::Failed
+        } else if self.passed > 0 {
+            PairStatus::Passed
+        } else if self.skipped > 0 {
+            PairStatus::Skipped
+        } else {
+            PairStatus::Untested
+        }
+    }
+}
+
+/// The color of a cell in the feature matrix.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PairStatus {
+    /// The exact pair passed, or every check containing the pair passed.
+    Passed,
+    /// The exact pair failed, or at least one check containing the pair failed.
+    Failed,
+    /// The pair was never checked, because every combination containing it was skipped.
+    Skipped,
+    /// No combination containing the pair was generated, such as due to `max_combo_size`, or
+    /// checking stopped early.
+    Untested,
+}
+
+impl PairStatus {
+    fn class(self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+            Self::Untested => "untested",
+        }
+    }
+}
+
+/// The [`PairCounts`] of every pair of features within a package, stored as a square matrix.
+struct PairMatrix {
+    size: usize,
+    counts: Vec<PairCounts>,
+}
+
+impl PairMatrix {
+    fn new(package: &PackageReport) -> Self {
+        let size = package.features.len();
+
+        let indices: HashMap<&str, usize> = package
+            .features
+            .iter()
+            .enumerate()
+            .map(|(i, feature)| (feature.as_str(), i))
+            .collect();
+
+        let mut matrix = Self {
+            size,
+            counts: vec![PairCounts::default(); size * size],
+        };
+
+        let checks = package
+            .checks
+            .iter()
+            .map(|c| (&c.features, Some(&c.outcome)));
+        let skipped = package.skipped.iter().map(|s| (&s.features, None));
+
+        for (features, outcome) in checks.chain(skipped) {
+            let combo: Vec<usize> = features
+                .iter()
+                .filter_map(|f| indices.get(f.as_str()).copied())
+                .collect();
+
+            for &i in &combo {
+                for &j in &combo {
+                    let counts = &mut matrix.counts[i * size + j];
+
+                    match outcome {
+                        Some(Outcome::Passed) => counts.passed += 1,
+                        Some(_) => counts.failed += 1,
+                        None => counts.skipped += 1,
+                    }
+
+                    // The combo is exactly this pair, or exactly this feature on the diagonal.
+                    let is_exact = combo.len() == if i == j { 1 } else { 2 };
+
+                    if let (true, Some(outcome)) = (is_exact, outcome) {
+                        let passed = *outcome == Outcome::Passed;
+                        counts.exact_passed = Some(counts.exact_passed.unwrap_or(true) && passed);
+                    }
+                }
+            }
+        }
+
+        matrix
+    }
+
+    fn get(&self, i: usize, j: usize) -> PairCounts {
+        self.counts[i * self.size + j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combos::SkipReason,
+        report::{CheckReport, SkippedCombo},
+        runner::Platform,
+    };
+
+    #[test]
+    fn pair_matrix() {
+        let check = |features: &[&str], outcome| CheckReport {
+            features: features.iter().map(|f| f.to_string()).collect(),
+            platform: Platform::default(),
+            outcome,
+            cached: false,
+            duration_secs: Some(1.0),
+        };
+
+        let failed = || Outcome::Failed {
+            command: "cargo-check".to_string(),
+        };
+
+        let package = PackageReport {
+            name: "foo".to_string(),
+            features: ["a", "b", "c", "d"].map(str::to_string).to_vec(),
+            estimated_checks: 16,
+            actual_checks: 4,
+            checks: vec![
+                check(&["a"], Outcome::Passed),
+                check(&["b"], Outcome::Passed),
+                check(&["a", "b"], failed()),
+                check(&["c"], Outcome::Passed),
+            ],
+            skipped: vec![SkippedCombo {
+                features: vec!["a".to_string(), "c".to_string()],
+                platform: Platform::default(),
+                reason: SkipReason::Rule { rule: 0 },
+            }],
+        };
+
+        let matrix = PairMatrix::new(&package);
+
+        // `a` passed alone, but failed with `b`.
+        assert_eq!(matrix.get(0, 0).status(), PairStatus::Passed);
+        assert_eq!(matrix.get(0, 0).failed, 1);
+        assert_eq!(matrix.get(0, 1).status(), PairStatus::Failed);
+        assert_eq!(matrix.get(1, 0).status(), PairStatus::Failed);
+        assert_eq!(matrix.get(2, 2).status(), PairStatus::Passed);
+        assert_eq!(matrix.get(0, 2).status(), PairStatus::Skipped);
+        assert_eq!(matrix.get(1, 2).status(), PairStatus::Untested);
+        assert_eq!(matrix.get(3, 3).status(), PairStatus::Untested);
+    }
+}
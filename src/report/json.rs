@@ -0,0 +1,22 @@
+//! Writing a [`Report`] as JSON.
+
+use super::Report;
+use anyhow::Context;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Writes a [`Report`] to a file as pretty-printed JSON.
+pub fn write(report: &Report<'_>, path: &Path) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {path:?}."))?;
+    let mut writer = BufWriter::new(file);
+
+    serde_json::to_writer_pretty(&mut writer, report)
+        .with_context(|| format!("Failed to write JSON report to {path:?}."))?;
+
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+//! Writing a [`Report`] as [JUnit XML](https://github.com/testmoapp/junitxml), which many CI
+//! services can display.
+//!
+//! Each package is a `<testsuite>`, and each checked or skipped combination is a `<testcase>`.
+
+use super::{escape, Report};
+use crate::{
+    runner::{Outcome, Platform},
+    CheckFailure,
+};
+use anyhow::Context;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Writes a [`Report`] to a file as JUnit XML.
+pub fn write(report: &Report<'_>, path: &Path) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {path:?}."))?;
+    let mut writer = BufWriter::new(file);
+
+    write_xml(report, &mut writer)
+        .and_then(|()| writer.flush())
+        .with_context(|| format!("Failed to write JUnit report to {path:?}."))
+}
+
+fn write_xml(report: &Report<'_>, w: &mut impl Write) -> io::Result<()> {
+    // Failed checks are matched with their diagnostics using the package, features and platform.
+    let failures: HashMap<_, _> = report
+        .failures
+        .iter()
+        .map(|failure| {
+            let key = (&failure.package, &failure.features, &failure.platform);
+            (key, failure)
+        })
+        .collect();
+
+    let total_tests: usize = report
+        .packages
+        .iter()
+        .map(|p| p.checks.len() + p.skipped.len())
+        .sum();
+    let total_failures: usize = report
+        .packages
+        .iter()
+        .flat_map(|p| &p.checks)
+        .filter(|c| c.outcome != Outcome::Passed)
+        .count();
+    let total_skipped: usize = report.packages.iter().map(|p| p.skipped.len()).sum();
+    let total_time: f64 = report
+        .packages
+        .iter()
+        .flat_map(|p| &p.checks)
+        .filter_map(|c| c.duration_secs)
+        .sum();
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<testsuites name="flag-frenzy" tests="{total_tests}" failures="{total_failures}" skipped="{total_skipped}" time="{total_time:.3}">"#
+    )?;
+
+    for package in &report.packages {
+        let name = escape(&package.name);
+        let failures_count = package
+            .checks
+            .iter()
+            .filter(|c| c.outcome != Outcome::Passed)
+            .count();
+        let time: f64 = package.checks.iter().filter_map(|c| c.duration_secs).sum();
+
+        writeln!(
+            w,
+            r#"  <testsuite name="{name}" tests="{}" failures="{failures_count}" errors="0" skipped="{}" time="{time:.3}">"#,
+            package.checks.len() + package.skipped.len(),
+            package.skipped.len(),
+        )?;
+
+        for check in &package.checks {
+            let case_name = escape(case_name(&check.features, &check.platform));
+            let time = check.duration_secs.unwrap_or_default();
+
+            write!(
+                w,
+                r#"    <testcase classname="{name}" name="{case_name}" time="{time:.3}""#
+            )?;
+
+            let (kind, command) = match &check.outcome {
+                Outcome::Passed => {
+                    writeln!(w, "/>")?;
+                    continue;
+                }
+                Outcome::Failed { command } => ("failed", command),
+                Outcome::TimedOut { command } => ("timed_out", command),
+            };
+
+            writeln!(w, ">")?;
+
+            let message = match check.outcome {
+                Outcome::TimedOut { .. } => format!("Timed out running {command}"),
+                _ => format!("Failed {command}"),
+            };
+
+            write!(
+                w,
+                r#"      <failure message="{}" type="{kind}">"#,
+                escape(message)
+            )?;
+
+            let key = (&package.name, &check.features, &check.platform);
+
+            if let Some(CheckFailure { diagnostics, .. }) = failures.get(&key) {
+                for diagnostic in diagnostics {
+                    writeln!(w, "{}", escape(diagnostic))?;
+                }
+            }
+
+            writeln!(w, "</failure>")?;
+            writeln!(w, "    </testcase>")?;
+        }
+
+        for skipped in &package.skipped {
+            let case_name = escape(case_name(&skipped.features, &skipped.platform));
+
+            writeln!(
+                w,
+                r#"    <testcase classname="{name}" name="{case_name}" time="0.000">"#
+            )?;
+            writeln!(
+                w,
+                r#"      <skipped message="{}"/>"#,
+                escape(&skipped.reason)
+            )?;
+            writeln!(w, "    </testcase>")?;
+        }
+
+        writeln!(w, "  </testsuite>")?;
+    }
+
+    writeln!(w, "</testsuites>")
+}
+
+/// Returns the name of a test case, such as `bar,foo on nightly`.
+fn case_name(features: &[String], platform: &Platform) -> String {
+    if features.is_empty() {
+        format!("(no features){platform}")
+    } else {
+        format!("{}{platform}", features.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combos::SkipReason,
+        diagnostics::Diagnostic,
+        report::{CheckReport, PackageReport, SkippedCombo},
+    };
+
+    #[test]
+    fn junit_xml() {
+        let failures = [CheckFailure {
+            package: "foo".to_string(),
+            platform: Platform::default(),
+            features: vec!["bar".to_string()],
+            outcome: Outcome::Failed {
+                command: "cargo-check".to_string(),
+            },
+            diagnostics: vec![Diagnostic {
+                code: None,
+                message: "oops".to_string(),
+                span: None,
+            }],
+            minimized: None,
+            baseline: None,
+        }];
+
+        let report = Report {
+            packages: vec![PackageReport {
+                name: "foo".to_string(),
+                features: vec!["bar".to_string(), "baz".to_string()],
+                estimated_checks: 4,
+                actual_checks: 2,
+                checks: vec![
+                    CheckReport {
+                        features: Vec::new(),
+                        platform: Platform::default(),
+                        outcome: Outcome::Passed,
+                        cached: false,
+                        duration_secs: Some(1.5),
+                    },
+                    CheckReport {
+                        features: vec!["bar".to_string()],
+                        platform: Platform::default(),
+                        outcome: failures[0].outcome.clone(),
+                        cached: false,
+                        duration_secs: Some(0.25),
+                    },
+                ],
+                skipped: vec![SkippedCombo {
+                    features: vec!["baz".to_string()],
+                    platform: Platform::default(),
+                    reason: SkipReason::Rule { rule: 0 },
+                }],
+            }],
+            failures: &failures,
+            unchecked_checks: 0,
+        };
+
+        let mut xml = Vec::new();
+        write_xml(&report, &mut xml).unwrap();
+
+        assert_eq!(
+            String::from_utf8(xml).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="flag-frenzy" tests="3" failures="1" skipped="1" time="1.750">
+  <testsuite name="foo" tests="3" failures="1" errors="0" skipped="1" time="1.750">
+    <testcase classname="foo" name="(no features)" time="1.500"/>
+    <testcase classname="foo" name="bar" time="0.250">
+      <failure message="Failed cargo-check" type="failed">error: oops
+</failure>
+    </testcase>
+    <testcase classname="foo" name="baz" time="0.000">
+      <skipped message="skipped by rule 0"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
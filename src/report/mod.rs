@@ -0,0 +1,110 @@
+//! Structured reports of a run, for tools that cannot parse the terminal output.
+//!
+//! A [`Report`] is built while checking, then written in each format requested by the CLI.
+
+pub mod html;
+pub mod json;
+pub mod junit;
+pub mod sarif;
+
+use crate::{
+    combos::SkipReason,
+    runner::{Outcome, Platform},
+    CheckFailure,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Everything that happened during a run.
+#[derive(Serialize, Debug)]
+pub struct Report<'a> {
+    /// Every package that was checked during this run, in order.
+    ///
+    /// Packages that were completely checked before the run was resumed are not included.
+    pub packages: Vec<PackageReport>,
+    /// All failures, including those found before the run was resumed.
+    pub failures: &'a [CheckFailure],
+    /// The amount of checks that were never run, because checking stopped early.
+    pub unchecked_checks: usize,
+}
+
+/// The checks of a single package.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PackageReport {
+    pub name: String,
+    /// Every feature that combos are made from, sorted by name.
+    pub features: Vec<String>,
+    /// The amount of checks estimated before rules were applied.
+    pub estimated_checks: u128,
+    /// The amount of checks that were completed, including those that were cached or completed
+    /// before the run was resumed.
+    pub actual_checks: usize,
+    /// Every check completed during this run, in order.
+    pub checks: Vec<CheckReport>,
+    /// Every combo that was not checked because of the config, in order.
+    pub skipped: Vec<SkippedCombo>,
+}
+
+/// A single feature combination checked on a single platform.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CheckReport {
+    /// The enabled features, sorted by name.
+    pub features: Vec<String>,
+    #[serde(flatten)]
+    pub platform: Platform,
+    pub outcome: Outcome,
+    /// True if the check passed in a previous run, so it was not run again.
+    pub cached: bool,
+    /// How long the check took to run in seconds, or [`None`] if it was cached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+}
+
+/// A feature combination that was not checked, either entirely or on a certain platform.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SkippedCombo {
+    /// The features of the combo, sorted by name.
+    pub features: Vec<String>,
+    /// The platform the combo was skipped on, which is the default if it was skipped entirely.
+    #[serde(flatten)]
+    pub platform: Platform,
+    #[serde(flatten)]
+    pub reason: SkipReason,
+}
+
+/// Escapes text so that it can be placed within an XML or HTML attribute or element.
+///
+/// Control characters other than tabs and newlines are not allowed in XML 1.0, so they are
+/// removed.
+fn escape(text: impl Display) -> String {
+    let text = text.to_string();
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_xml() {
+        assert_eq!(
+            escape("expected `&str`, found <T as \"Foo\">\u{1b}"),
+            "expected `&amp;str`, found &lt;T as &quot;Foo&quot;&gt;"
+        );
+    }
+}
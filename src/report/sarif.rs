@@ -0,0 +1,224 @@
+//! Writing the diagnostics of a [`Report`] as [SARIF](https://sarifweb.azurewebsites.net/), which
+//! code scanning tools can use to annotate the lines that fail.
+//!
+//! Each unique compiler error becomes a single result, listing every combination that caused it.
+
+use super::Report;
+use crate::{diagnostics::Diagnostic, CheckFailure};
+use anyhow::Context;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// The rule used for errors without an error code.
+const UNCODED_RULE: &str = "rustc";
+
+/// Writes the diagnostics of a [`Report`] to a file as SARIF.
+pub fn write(report: &Report<'_>, path: &Path) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {path:?}."))?;
+    let mut writer = BufWriter::new(file);
+
+    serde_json::to_writer_pretty(&mut writer, &sarif(report.failures))
+        .with_context(|| format!("Failed to write SARIF report to {path:?}."))?;
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Converts the diagnostics of failed checks into a SARIF log.
+fn sarif(failures: &[CheckFailure]) -> Value {
+    // Group failures by their diagnostic, in the order each diagnostic was first found.
+    let mut unique: Vec<(&Diagnostic, Vec<&CheckFailure>)> = Vec::new();
+    let mut indices = HashMap::new();
+
+    for failure in failures {
+        for diagnostic in &failure.diagnostics {
+            let i = *indices.entry(diagnostic).or_insert_with(|| {
+                unique.push((diagnostic, Vec::new()));
+                unique.len() - 1
+            });
+
+            unique[i].1.push(failure);
+        }
+    }
+
+    let mut rule_ids: Vec<&str> = unique
+        .iter()
+        .map(|(diagnostic, _)| diagnostic.code.as_deref().unwrap_or(UNCODED_RULE))
+        .collect();
+
+    rule_ids.sort_unstable();
+    rule_ids.dedup();
+
+    let rules: Vec<Value> = rule_ids.iter().map(|id| json!({ "id": id })).collect();
+
+    let results: Vec<Value> = unique
+        .iter()
+        .map(|(diagnostic, failures)| result(diagnostic, failures))
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Creates a SARIF result for a single diagnostic, caused by one or more failures.
+fn result(diagnostic: &Diagnostic, failures: &[&CheckFailure]) -> Value {
+    let first = failures[0];
+
+    let mut text = format!(
+        "{} (package `{}` with `--features {}`{}",
+        diagnostic.message,
+        first.package,
+        first.features.join(","),
+        first.platform,
+    );
+
+    match failures.len() {
+        1 => text.push(')'),
+        2 => text.push_str(" and 1 other combination)"),
+        n => text.push_str(&format!(" and {} other combinations)", n - 1)),
+    }
+
+    let combinations: Vec<Value> = failures
+        .iter()
+        .map(|failure| {
+            let mut combination = json!({
+                "package": failure.package,
+                "features": failure.features,
+            });
+
+            // Merge in the toolchain and target, if there are any.
+            if let (Value::Object(combination), Value::Object(platform)) =
+                (&mut combination, json!(failure.platform))
+            {
+                combination.extend(platform);
+            }
+
+            combination
+        })
+        .collect();
+
+    let mut result = json!({
+        "ruleId": diagnostic.code.as_deref().unwrap_or(UNCODED_RULE),
+        "level": "error",
+        "message": { "text": text },
+        "properties": { "combinations": combinations },
+    });
+
+    if let Some(span) = &diagnostic.span {
+        result["locations"] = json!([{
+            "physicalLocation": {
+                "artifactLocation": {
+                    "uri": span.file_name.replace('\\', "/"),
+                    "uriBaseId": "%SRCROOT%",
+                },
+                "region": {
+                    "startLine": span.line,
+                    "startColumn": span.column,
+                },
+            },
+        }]);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostics::Span,
+        runner::{Outcome, Platform},
+    };
+
+    #[test]
+    fn deduplicates_diagnostics() {
+        let missing = Diagnostic {
+            code: Some("E0425".to_string()),
+            message: "cannot find function `missing` in this scope".to_string(),
+            span: Some(Span {
+                file_name: "src/lib.rs".to_string(),
+                line: 4,
+                column: 14,
+            }),
+        };
+
+        let linking = Diagnostic {
+            code: None,
+            message: "linking with `cc` failed".to_string(),
+            span: None,
+        };
+
+        let failure = |features: &[&str], diagnostics: Vec<Diagnostic>| CheckFailure {
+            package: "foo".to_string(),
+            platform: Platform::default(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            outcome: Outcome::Failed {
+                command: "cargo-check".to_string(),
+            },
+            diagnostics,
+            minimized: None,
+            baseline: None,
+        };
+
+        let failures = [
+            failure(&["bar"], vec![missing.clone(), linking]),
+            failure(&["bar", "baz"], vec![missing]),
+        ];
+
+        let log = sarif(&failures);
+        let run = &log["runs"][0];
+
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{ "id": "E0425" }, { "id": "rustc" }]),
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "E0425",
+                "level": "error",
+                "message": {
+                    "text": "cannot find function `missing` in this scope (package `foo` with `--features bar` and 1 other combination)",
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/lib.rs", "uriBaseId": "%SRCROOT%" },
+                        "region": { "startLine": 4, "startColumn": 14 },
+                    },
+                }],
+                "properties": {
+                    "combinations": [
+                        { "package": "foo", "features": ["bar"] },
+                        { "package": "foo", "features": ["bar", "baz"] },
+                    ],
+                },
+            }),
+        );
+
+        assert_eq!(results[1]["ruleId"], "rustc");
+        assert!(results[1].get("locations").is_none());
+    }
+}
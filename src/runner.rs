@@ -1,40 +1,644 @@
-use crate::intern::{FeatureKey, FeatureStorage};
-use anyhow::Context;
+use crate::{
+    config::{schema::CheckCommand, CheckBackend},
+    diagnostics::{parse_output, Diagnostic, ParsedOutput},
+    intern::{FeatureKey, FeatureStorage},
+    manifest::Target,
+    process::{output_with_deadline, TimedOutput},
+};
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::{
     ffi::OsStr,
+    fmt::{self, Display},
     path::Path,
-    process::{Command, ExitStatus},
+    process::{Command, Output},
+    time::{Duration, Instant},
 };
 
-/// Runs `cargo-check` on a package with the specified features.
+/// Whether a feature combination passed its checks.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Outcome {
+    /// All commands passed.
+    Passed,
+    /// A command exited with a non-zero exit code.
+    Failed {
+        /// The name of the command that failed.
+        command: String,
+    },
+    /// A command was still running when the timeout was reached, so it was killed.
+    TimedOut {
+        /// The name of the command that was killed.
+        command: String,
+    },
+}
+
+/// What a feature combination is compiled for.
+///
+/// A package is checked once per platform for every feature combination.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Platform {
+    /// The toolchain passed to Cargo as `+toolchain`, or [`None`] for the default toolchain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    /// The target triple passed to Cargo with `--target`, or [`None`] for the host target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl Display for Platform {
+    /// Formats the platform as a suffix for messages, such as
+    /// ` on nightly for target wasm32-unknown-unknown`.
+    ///
+    /// Nothing is written for the default toolchain and host target.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(toolchain) = &self.toolchain {
+            write!(f, " on {toolchain}")?;
+        }
+
+        if let Some(target) = &self.target {
+            write!(f, " for target {target}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Everything needed to check a package, apart from the features and platform.
+#[derive(Debug)]
+pub struct CheckOptions<'a> {
+    /// What is run to check each combination.
+    pub backend: CheckBackend<'a>,
+    /// The name of the package.
+    pub package: &'a str,
+    /// The path to the workspace's `Cargo.toml`.
+    pub manifest_path: &'a Path,
+    /// If a check takes longer than this, the running command and all of its child processes will
+    /// be killed. This applies to all commands combined, not each one individually.
+    pub timeout: Option<Duration>,
+    /// Extra arguments passed to every Cargo command.
+    pub cargo_args: Vec<String>,
+    /// Configuration values passed to every Cargo command with `--config`.
+    pub cargo_config: &'a [String],
+    /// If specified, the `RUSTFLAGS` environmental variable is set to this for every command.
+    ///
+    /// This includes any flags that were already set in the environment.
+    pub rustflags: Option<String>,
+    /// If specified, all of these targets are checked instead of just the library, except those
+    /// whose required features are not enabled.
+    pub all_targets: Option<&'a [Target]>,
+}
+
+impl CheckOptions<'_> {
+    /// Returns a string that describes everything that can affect whether a check passes, for use
+    /// with [`PackageCache`](crate::cache::PackageCache).
+    ///
+    /// `rustc_version` should be the output of [`rustc_version()`] for the platform, since its
+    /// toolchain may be unset or a channel like `stable` that is updated over time.
+    ///
+    /// This is written out explicitly as JSON, so that it only changes when one of these options
+    /// does.
+    pub fn cache_key(&self, platform: &Platform, rustc_version: &str) -> String {
+        let backend = match self.backend {
+            CheckBackend::Cargo(commands) => {
+                let commands: Vec<String> = commands.iter().map(|c| c.to_string()).collect();
+                json!({ "commands": commands })
+            }
+            CheckBackend::Template(template) => json!({ "command": template }),
+        };
+
+        json!({
+            "backend": backend,
+            "cargo_args": self.cargo_args,
+            "cargo_config": self.cargo_config,
+            "rustflags": self.rustflags,
+            "all_targets": self.all_targets.is_some(),
+            "toolchain": platform.toolchain,
+            "target": platform.target,
+            "rustc": rustc_version,
+        })
+        .to_string()
+    }
+}
+
+/// Returns the output of `rustc -vV` for the toolchain of a [`Platform`], which includes the exact
+/// version and commit of the compiler.
+pub fn rustc_version(platform: &Platform) -> anyhow::Result<String> {
+    let mut rustc = Command::new("rustc");
+    rustc.arg("-vV");
+
+    // `rustup` picks the toolchain from this, just like it does for `cargo +toolchain`.
+    if let Some(ref toolchain) = platform.toolchain {
+        rustc.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+
+    let output = rustc
+        .output()
+        .with_context(|| format!("Failed to run `rustc -vV`{platform}."))?;
+
+    ensure!(
+        output.status.success(),
+        "`rustc -vV`{platform} failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The result of running a package's commands on a single feature combination.
+pub struct CheckResult {
+    /// Whether all commands passed, and which one did not if they didn't.
+    pub outcome: Outcome,
+    /// All errors emitted by the compiler, in order.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The combined standard output of all commands that were run.
+    ///
+    /// JSON messages from Cargo are replaced with their human-readable form.
+    pub stdout: Vec<u8>,
+    /// The combined standard error of all commands that were run.
+    pub stderr: Vec<u8>,
+    /// How long it took to run all commands.
+    pub duration: Duration,
+}
+
+/// Checks a package with the specified features on a [`Platform`], using the given
+/// [`CheckOptions`].
+///
+/// For [`CheckBackend::Cargo`], each command is run in order, stopping at the first one that
+/// fails. For [`CheckBackend::Template`], the placeholders are filled in and the command is run
+/// once.
+///
+/// If `target_dir` is specified, it will be passed to Cargo with `--target-dir`, or to external
+/// commands as the `CARGO_TARGET_DIR` environmental variable. This lets multiple checks run at the
+/// same time without waiting on each other's build directory lock. Similarly, the platform's
+/// toolchain and target are passed to external commands as the `RUSTUP_TOOLCHAIN` and
+/// `CARGO_BUILD_TARGET` environmental variables.
+///
+/// The output of each command is captured and returned, rather than printed directly to the
+/// terminal. Any compiler errors within the output are parsed into [`Diagnostic`]s.
 pub fn check_with_features(
-    package: &str,
-    manifest_path: &Path,
+    options: &CheckOptions<'_>,
+    platform: &Platform,
+    target_dir: Option<&Path>,
     features: &[FeatureKey],
     storage: &FeatureStorage,
-) -> anyhow::Result<ExitStatus> {
-    // Create comma-separated list of features.
-    let features =
+) -> anyhow::Result<CheckResult> {
+    let start = Instant::now();
+    let deadline = options.timeout.map(|timeout| start + timeout);
+
+    let target_args = match options.all_targets {
+        Some(targets) => select_targets(targets, features, storage),
+        None => Vec::new(),
+    };
+
+    // Create comma-separated list of features, skipping keys that don't exist.
+    let features = features
+        .iter()
+        .filter_map(|key| storage.get(*key))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut result = CheckResult {
+        outcome: Outcome::Passed,
+        diagnostics: Vec::new(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        duration: Duration::ZERO,
+    };
+
+    match options.backend {
+        CheckBackend::Cargo(commands) => {
+            for &command in commands {
+                let name = format!("cargo-{command}");
+                let mut cargo = cargo_command(
+                    command,
+                    options,
+                    platform,
+                    target_dir,
+                    &features,
+                    &target_args,
+                );
+
+                if let Some(ref rustflags) = options.rustflags {
+                    cargo.env("RUSTFLAGS", rustflags);
+                }
+
+                if !run(cargo, name, deadline, &mut result)? {
+                    break;
+                }
+            }
+        }
+        CheckBackend::Template(template) => {
+            let mut command =
+                template_command(template, options.package, options.manifest_path, &features);
+
+            if let Some(target_dir) = target_dir {
+                command.env("CARGO_TARGET_DIR", target_dir);
+            }
+
+            if let Some(ref toolchain) = platform.toolchain {
+                command.env("RUSTUP_TOOLCHAIN", toolchain);
+            }
+
+            if let Some(ref target) = platform.target {
+                command.env("CARGO_BUILD_TARGET", target);
+            }
+
+            if let Some(ref rustflags) = options.rustflags {
+                command.env("RUSTFLAGS", rustflags);
+            }
+
+            run(command, template[0].clone(), deadline, &mut result)?;
+        }
+    }
+
+    result.duration = start.elapsed();
+
+    Ok(result)
+}
+
+/// Runs a [`Command`] to completion or until `deadline`, appending its output to a
+/// [`CheckResult`].
+///
+/// Returns true if the command succeeded. If it failed or timed out, [`CheckResult::outcome`] is
+/// updated with `name`.
+fn run(
+    mut command: Command,
+    name: String,
+    deadline: Option<Instant>,
+    result: &mut CheckResult,
+) -> anyhow::Result<bool> {
+    let TimedOutput {
+        output: Output {
+            status,
+            stdout,
+            stderr,
+        },
+        timed_out,
+    } = output_with_deadline(&mut command, deadline)
+        .with_context(|| format!("Failed to spawn `{name}`."))?;
+
+    let ParsedOutput {
+        diagnostics,
+        rendered,
+    } = parse_output(&stdout);
+
+    result.diagnostics.extend(diagnostics);
+    result.stdout.extend(rendered);
+    result.stderr.extend(stderr);
+
+    if timed_out {
+        result.outcome = Outcome::TimedOut { command: name };
+        return Ok(false);
+    }
+
+    if !status.success() {
+        result.outcome = Outcome::Failed { command: name };
+    }
+
+    Ok(status.success())
+}
+
+/// Returns the arguments that select every target of a package that can be built with the given
+/// features.
+///
+/// If the required features of every target are enabled, this is just `--all-targets`. Otherwise,
+/// each target that can be built is selected individually, since Cargo cannot exclude targets.
+fn select_targets(
+    targets: &[Target],
+    features: &[FeatureKey],
+    storage: &FeatureStorage,
+) -> Vec<String> {
+    // A feature is enabled if it is in the combo, or if a feature in the combo depends on it.
+    let is_enabled = |feature: &String| {
+        let key = storage.create_key(feature);
         features
             .iter()
-            // Convert `FeatureKey`s to `&str`, skipping keys that don't exist.
-            .filter_map(|key| storage.get(*key))
-            // Concatenate all strings together, with a `,` separating them. This will result in an
-            // extra comma at the end, but Cargo still accepts this.
-            .fold(String::new(), |mut acc, f| {
-                acc.push_str(f);
-                acc.push(',');
-                acc
-            });
-
-    Command::new("cargo")
-        .arg("check")
-        .args([OsStr::new("--manifest-path"), manifest_path.as_os_str()])
-        .args(["--package", package])
+            .any(|&enabled| enabled == key || storage.is_dependency(enabled, key))
+    };
+
+    let (buildable, excluded): (Vec<_>, Vec<_>) = targets
+        .iter()
+        .partition(|target| target.required_features.iter().all(is_enabled));
+
+    if excluded.is_empty() {
+        return vec!["--all-targets".to_string()];
+    }
+
+    let mut args = Vec::new();
+
+    for target in buildable {
+        match target.kind[0].as_str() {
+            kind @ ("bin" | "example" | "test" | "bench") => {
+                args.push(format!("--{kind}"));
+                args.push(target.name.clone());
+            }
+            // Build scripts are always built, and cannot be selected.
+            "custom-build" => {}
+            // All other kinds are libraries, such as `rlib` or `proc-macro`.
+            _ => args.push("--lib".to_string()),
+        }
+    }
+
+    args
+}
+
+/// Returns the arguments from [`select_targets()`] that `cargo doc` accepts.
+///
+/// `cargo doc` cannot document tests or benchmarks, and does not accept `--all-targets`. Without
+/// any arguments, it documents the library and binaries.
+fn doc_targets(target_args: &[String]) -> Vec<&String> {
+    let mut args = Vec::new();
+    let mut iter = target_args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--lib" => args.push(arg),
+            "--bin" | "--example" => args.extend([arg].into_iter().chain(iter.next())),
+            "--test" | "--bench" => {
+                iter.next();
+            }
+            _ => {}
+        }
+    }
+
+    args
+}
+
+/// Creates a Cargo subcommand for a package with a comma-separated list of features.
+///
+/// `target_args` select which targets are checked, and should be created by [`select_targets()`].
+fn cargo_command(
+    command: CheckCommand,
+    options: &CheckOptions<'_>,
+    platform: &Platform,
+    target_dir: Option<&Path>,
+    features: &str,
+    target_args: &[String],
+) -> Command {
+    let mut cargo = Command::new("cargo");
+
+    // The toolchain must be the very first argument, so that `rustup` can intercept it.
+    if let Some(ref toolchain) = platform.toolchain {
+        cargo.arg(format!("+{toolchain}"));
+    }
+
+    // `--config` must be passed before the subcommand.
+    for config in options.cargo_config {
+        cargo.args(["--config", config]);
+    }
+
+    cargo
+        .arg(command.to_string())
+        .args([
+            OsStr::new("--manifest-path"),
+            options.manifest_path.as_os_str(),
+        ])
+        .args(["--package", options.package])
         .arg("--no-default-features")
-        .args(["--features", &features])
+        .args(["--features", features])
         .arg("--quiet")
-        .args(["--message-format", "short"])
-        .status()
-        .context("Failed to spawn `cargo-check`.")
+        // Output JSON so errors can be parsed, but keep the human-readable form short.
+        .args(["--message-format", "json-diagnostic-short"]);
+
+    if let Some(ref target) = platform.target {
+        cargo.args(["--target", target]);
+    }
+
+    if let Some(target_dir) = target_dir {
+        cargo.args([OsStr::new("--target-dir"), target_dir.as_os_str()]);
+    }
+
+    // These must be passed before the `--` of `cargo-clippy`.
+    if command == CheckCommand::Doc {
+        cargo.args(doc_targets(target_args));
+    } else {
+        cargo.args(target_args);
+    }
+    cargo.args(&options.cargo_args);
+
+    match command {
+        CheckCommand::Check => {}
+        // Treat all lints as errors, so that they fail the check.
+        CheckCommand::Clippy => {
+            cargo.args(["--", "-D", "warnings"]);
+        }
+        // Only build the tests, don't run them.
+        CheckCommand::Test => {
+            cargo.arg("--no-run");
+        }
+        // Only document this package, not its dependencies.
+        CheckCommand::Doc => {
+            cargo.arg("--no-deps");
+        }
+    }
+
+    cargo
+}
+
+/// Creates a [`Command`] from a template, filling in its placeholders.
+///
+/// The first element of the template is the program, and the rest are its arguments. The following
+/// placeholders are supported, and may appear anywhere within an argument:
+///
+/// - `{package}`: The name of the package.
+/// - `{features}`: A comma-separated list of features, which may be empty.
+/// - `{manifest_path}`: The path to the workspace's `Cargo.toml`.
+fn template_command(
+    template: &[String],
+    package: &str,
+    manifest_path: &Path,
+    features: &str,
+) -> Command {
+    let manifest_path = manifest_path.to_string_lossy();
+
+    let fill = |arg: &String| {
+        arg.replace("{package}", package)
+            .replace("{features}", features)
+            .replace("{manifest_path}", &manifest_path)
+    };
+
+    let mut command = Command::new(fill(&template[0]));
+    command.args(template[1..].iter().map(fill));
+
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn cache_key() {
+        let options = CheckOptions {
+            backend: CheckBackend::Cargo(&[CheckCommand::Check, CheckCommand::Clippy]),
+            package: "foo",
+            manifest_path: Path::new("Cargo.toml"),
+            timeout: None,
+            cargo_args: vec!["--locked".to_string()],
+            cargo_config: &[],
+            rustflags: Some("-D warnings".to_string()),
+            all_targets: None,
+        };
+
+        let platform = Platform {
+            toolchain: Some("nightly".to_string()),
+            target: None,
+        };
+
+        // Changing this format invalidates every cache, so it should only change on purpose.
+        assert_eq!(
+            options.cache_key(&platform, "rustc 1.80.0"),
+            r#"{"all_targets":false,"backend":{"commands":["check","clippy"]},"cargo_args":["--locked"],"cargo_config":[],"rustc":"rustc 1.80.0","rustflags":"-D warnings","target":null,"toolchain":"nightly"}"#,
+        );
+
+        let key = options.cache_key(&platform, "rustc 1.80.0");
+
+        // Updating the toolchain invalidates the cache, even if its name stays the same.
+        assert_ne!(options.cache_key(&platform, "rustc 1.81.0"), key);
+
+        let without_flags = CheckOptions {
+            rustflags: None,
+            ..options
+        };
+
+        assert_ne!(without_flags.cache_key(&platform, "rustc 1.80.0"), key);
+    }
+
+    #[test]
+    fn fill_template() {
+        let template = [
+            "./ci/check.sh".to_string(),
+            "{package}".to_string(),
+            "--features={features}".to_string(),
+            "{manifest_path}".to_string(),
+        ];
+
+        let command = template_command(&template, "foo", Path::new("Cargo.toml"), "bar,baz");
+
+        assert_eq!(command.get_program(), "./ci/check.sh");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["foo", "--features=bar,baz", "Cargo.toml"],
+        );
+    }
+
+    #[test]
+    fn select_targets_by_required_features() {
+        fn target(name: &str, kind: &str, required_features: &[&str]) -> Target {
+            Target {
+                name: name.to_string(),
+                kind: vec![kind.to_string()],
+                required_features: required_features.iter().map(|f| f.to_string()).collect(),
+            }
+        }
+
+        let mut storage = FeatureStorage::new();
+
+        let mut features = HashMap::new();
+        features.insert("foo".to_string(), Vec::new());
+        features.insert("bar".to_string(), vec!["foo".to_string()]);
+
+        let foo = storage.insert("foo".to_string(), &features);
+        let bar = storage.insert("bar".to_string(), &features);
+
+        let targets = [
+            target("lib", "rlib", &[]),
+            target("build-script-build", "custom-build", &[]),
+            target("cli", "bin", &[]),
+            target("needs_foo", "example", &["foo"]),
+            target("integration", "test", &[]),
+        ];
+
+        assert_eq!(
+            select_targets(&targets, &[foo], &storage),
+            ["--all-targets"]
+        );
+
+        // `bar` enables `foo`, so examples that require `foo` can still be built.
+        assert_eq!(
+            select_targets(&targets, &[bar], &storage),
+            ["--all-targets"]
+        );
+
+        assert_eq!(
+            select_targets(&targets, &[], &storage),
+            ["--lib", "--bin", "cli", "--test", "integration"],
+        );
+    }
+
+    #[test]
+    fn doc_target_args() {
+        let options = CheckOptions {
+            backend: CheckBackend::Cargo(&[CheckCommand::Doc]),
+            package: "foo",
+            manifest_path: Path::new("Cargo.toml"),
+            timeout: None,
+            cargo_args: Vec::new(),
+            cargo_config: &[],
+            rustflags: None,
+            all_targets: None,
+        };
+
+        let platform = Platform {
+            toolchain: None,
+            target: None,
+        };
+
+        let args = |command, target_args: &[&str]| {
+            let target_args: Vec<String> = target_args.iter().map(|a| a.to_string()).collect();
+            let cargo = cargo_command(command, &options, &platform, None, "bar", &target_args);
+
+            cargo
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let common = [
+            "doc",
+            "--manifest-path",
+            "Cargo.toml",
+            "--package",
+            "foo",
+            "--no-default-features",
+            "--features",
+            "bar",
+            "--quiet",
+            "--message-format",
+            "json-diagnostic-short",
+        ];
+
+        assert_eq!(
+            args(CheckCommand::Doc, &["--all-targets"]),
+            [&common[..], &["--no-deps"]].concat()
+        );
+        assert_eq!(
+            args(
+                CheckCommand::Doc,
+                &[
+                    "--lib",
+                    "--bin",
+                    "cli",
+                    "--test",
+                    "integration",
+                    "--example",
+                    "demo",
+                    "--bench",
+                    "perf"
+                ],
+            ),
+            [
+                &common[..],
+                &["--lib", "--bin", "cli", "--example", "demo", "--no-deps"]
+            ]
+            .concat(),
+        );
+
+        // Other commands still check every selected target.
+        assert!(args(CheckCommand::Check, &["--test", "integration"])
+            .ends_with(&["--test".to_string(), "integration".to_string()]));
+    }
 }
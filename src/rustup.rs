@@ -0,0 +1,144 @@
+//! Querying `rustup` for what is installed locally.
+
+use crate::runner::Platform;
+use anyhow::{bail, ensure, Context};
+use std::{collections::HashMap, io, process::Command};
+
+/// The toolchains and targets that `rustup` reports as installed.
+///
+/// Each list is only queried the first time it is needed, then reused.
+#[derive(Default)]
+pub struct Installed {
+    /// Set if `rustup` could not be found, in which case nothing can be verified.
+    missing_rustup: bool,
+    /// The names of all installed toolchains, such as `stable-x86_64-unknown-linux-gnu`.
+    toolchains: Option<Vec<String>>,
+    /// The installed targets of each toolchain, where [`None`] is the default toolchain.
+    targets: HashMap<Option<String>, Vec<String>>,
+}
+
+impl Installed {
+    /// Returns an error if the toolchain or target of a [`Platform`] is not installed.
+    ///
+    /// If `rustup` could not be found, such as when Rust was installed through a system package
+    /// manager, a warning is printed once and everything is assumed to be installed.
+    pub fn verify(&mut self, platform: &Platform) -> anyhow::Result<()> {
+        if self.missing_rustup {
+            return Ok(());
+        }
+
+        if let Some(ref toolchain) = platform.toolchain {
+            if self.toolchains.is_none() {
+                let Some(toolchains) = rustup(&["toolchain", "list"])? else {
+                    return self.warn_missing_rustup();
+                };
+
+                self.toolchains = Some(toolchains);
+            }
+
+            let toolchains = self.toolchains.as_ref().unwrap();
+
+            // Toolchains are listed with their host triple, such as `1.79-x86_64-pc-windows-msvc`.
+            let is_installed = toolchains
+                .iter()
+                .any(|name| name == toolchain || name.starts_with(&format!("{toolchain}-")));
+
+            if !is_installed {
+                bail!("Toolchain {toolchain} is not installed. Install it with `rustup toolchain install {toolchain}`, or remove it from `toolchains` in the config.");
+            }
+        }
+
+        if let Some(ref target) = platform.target {
+            if !self.targets.contains_key(&platform.toolchain) {
+                let mut args = vec!["target", "list", "--installed"];
+
+                if let Some(ref toolchain) = platform.toolchain {
+                    args.extend(["--toolchain", toolchain]);
+                }
+
+                let Some(targets) = rustup(&args)? else {
+                    return self.warn_missing_rustup();
+                };
+
+                self.targets.insert(platform.toolchain.clone(), targets);
+            }
+
+            if !self.targets[&platform.toolchain].contains(target) {
+                match platform.toolchain {
+                    Some(ref toolchain) => bail!("Target {target} is not installed for toolchain {toolchain}. Install it with `rustup target add {target} --toolchain {toolchain}`, or remove it from `targets` in the config."),
+                    None => bail!("Target {target} is not installed. Install it with `rustup target add {target}`, or remove it from `targets` in the config."),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn warn_missing_rustup(&mut self) -> anyhow::Result<()> {
+        eprintln!("Could not find rustup, assuming all toolchains and targets are installed.");
+        self.missing_rustup = true;
+
+        Ok(())
+    }
+}
+
+/// Runs `rustup` with the given arguments, returning each item it lists.
+///
+/// Returns [`None`] if `rustup` could not be found.
+fn rustup(args: &[&str]) -> anyhow::Result<Option<Vec<String>>> {
+    let display = format!("rustup {}", args.join(" "));
+
+    let output = match Command::new("rustup").args(args).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to run `{display}`.")),
+    };
+
+    ensure!(
+        output.status.success(),
+        "`{display}` failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    Ok(Some(parse_list(&output.stdout)))
+}
+
+/// Parses the output of a `rustup` list command, which has one item per line.
+///
+/// Annotations after the item, such as `(default)`, are removed.
+fn parse_list(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_targets() {
+        let stdout = b"wasm32-unknown-unknown\nx86_64-unknown-linux-gnu\n\n";
+
+        assert_eq!(
+            parse_list(stdout),
+            ["wasm32-unknown-unknown", "x86_64-unknown-linux-gnu"],
+        );
+    }
+
+    #[test]
+    fn parse_toolchains() {
+        let stdout = b"stable-x86_64-unknown-linux-gnu (default)\nnightly-x86_64-unknown-linux-gnu (active)\n1.79-x86_64-unknown-linux-gnu\n";
+
+        assert_eq!(
+            parse_list(stdout),
+            [
+                "stable-x86_64-unknown-linux-gnu",
+                "nightly-x86_64-unknown-linux-gnu",
+                "1.79-x86_64-unknown-linux-gnu",
+            ],
+        );
+    }
+}
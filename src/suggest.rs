@@ -0,0 +1,339 @@
+//! Suggesting `[[rule]]` blocks that would skip the combinations that failed.
+//!
+//! These are only heuristics: a suggestion says that the checked combinations are consistent with
+//! a legitimate incompatibility, not that there is one. Each suggestion lists the failures it would
+//! explain, so that it can be verified before it is added to the config.
+
+use crate::{report::CheckReport, runner::Outcome};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+};
+
+/// The most failures listed in the comment above each suggested rule.
+const MAX_LISTED_FAILURES: usize = 5;
+
+/// A rule that would skip some failing combinations, without skipping any that passed.
+#[derive(PartialEq, Debug)]
+pub enum SuggestedRule {
+    /// Every combination containing `feature` failed.
+    Forbid { feature: String },
+    /// Every combination containing `feature` failed, unless it contained one of `any_of`.
+    Require {
+        feature: String,
+        any_of: Vec<String>,
+    },
+    /// Every combination containing both features failed.
+    ///
+    /// Each feature also passed in some combination without the other, since features that never
+    /// passed are forbidden on their own instead of in a pair.
+    Incompatible { first: String, second: String },
+}
+
+/// A suggested rule, along with the failing combinations it would skip.
+#[derive(Debug)]
+pub struct Suggestion {
+    pub rule: SuggestedRule,
+    /// The features of each failing combination this rule would skip, in the order they were
+    /// checked.
+    pub explains: Vec<Vec<String>>,
+}
+
+impl Display for Suggestion {
+    /// Formats the suggestion as a `[[rule]]` block, with a comment listing what it explains.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.explains.len();
+
+        write!(
+            f,
+            "# Explains {count} {}: ",
+            if count == 1 { "failure" } else { "failures" },
+        )?;
+
+        for (i, features) in self.explains.iter().take(MAX_LISTED_FAILURES).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{features:?}")?;
+        }
+
+        if count > MAX_LISTED_FAILURES {
+            write!(f, " and {} more", count - MAX_LISTED_FAILURES)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "[[rule]]")?;
+
+        match &self.rule {
+            SuggestedRule::Forbid { feature } => {
+                writeln!(f, "when = {feature:?}")?;
+                writeln!(f, "forbid = true")
+            }
+            SuggestedRule::Require { feature, any_of } => {
+                writeln!(f, "when = {feature:?}")?;
+
+                match &any_of[..] {
+                    [one] => writeln!(f, "require = {one:?}"),
+                    many => {
+                        let many: Vec<String> = many.iter().map(|f| format!("{f:?}")).collect();
+                        writeln!(f, "require = [{}]", many.join(", \"OR\", "))
+                    }
+                }
+            }
+            SuggestedRule::Incompatible { first, second } => {
+                writeln!(f, "when = {first:?}")?;
+                writeln!(f, "forbid = {second:?}")
+            }
+        }
+    }
+}
+
+/// Suggests rules that explain the failures among the checks of a single package.
+///
+/// A combination counts as failing if it failed on any platform. Rules for single features are
+/// suggested first, followed by pairs of features that are incompatible with each other. The
+/// second value is the amount of failing combinations that no suggestion explains.
+pub fn suggest_rules(checks: &[CheckReport]) -> (Vec<Suggestion>, usize) {
+    // Whether each combination failed, in the order they were first checked.
+    let mut combos: Vec<(BTreeSet<&str>, bool)> = Vec::new();
+    let mut indices: BTreeMap<&[String], usize> = BTreeMap::new();
+
+    for check in checks {
+        let i = *indices.entry(&check.features).or_insert_with(|| {
+            combos.push((check.features.iter().map(String::as_str).collect(), false));
+            combos.len() - 1
+        });
+
+        combos[i].1 |= check.outcome != Outcome::Passed;
+    }
+
+    let features: BTreeSet<&str> = combos.iter().flat_map(|(c, _)| c.iter().copied()).collect();
+
+    let mut suggestions = Vec::new();
+
+    // Features that already have a suggestion, so they are not suggested again in a pair.
+    let mut suggested = BTreeSet::new();
+
+    for &feature in &features {
+        let (failing, passing): (Vec<_>, Vec<_>) = combos
+            .iter()
+            .filter(|(c, _)| c.contains(feature))
+            .partition(|&&(_, failed)| failed);
+
+        if failing.is_empty() {
+            continue;
+        }
+
+        let rule = if passing.is_empty() {
+            SuggestedRule::Forbid {
+                feature: feature.to_string(),
+            }
+        } else {
+            match required_features(feature, &failing, &passing) {
+                Some(any_of) => SuggestedRule::Require {
+                    feature: feature.to_string(),
+                    any_of,
+                },
+                None => continue,
+            }
+        };
+
+        suggested.insert(feature);
+
+        suggestions.push(Suggestion {
+            rule,
+            explains: failing.iter().map(|(c, _)| to_strings(c)).collect(),
+        });
+    }
+
+    let features: Vec<&str> = features.into_iter().collect();
+
+    for (i, &first) in features.iter().enumerate() {
+        for &second in &features[i + 1..] {
+            if suggested.contains(first) || suggested.contains(second) {
+                continue;
+            }
+
+            let both: Vec<_> = combos
+                .iter()
+                .filter(|(c, _)| c.contains(first) && c.contains(second))
+                .collect();
+
+            // Neither feature is forbidden on its own, so each passed in some combination, which
+            // must have been without the other.
+            if both.is_empty() || !both.iter().all(|&&(_, failed)| failed) {
+                continue;
+            }
+
+            suggestions.push(Suggestion {
+                rule: SuggestedRule::Incompatible {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                },
+                explains: both.iter().map(|(c, _)| to_strings(c)).collect(),
+            });
+        }
+    }
+
+    let explained: BTreeSet<&Vec<String>> = suggestions.iter().flat_map(|s| &s.explains).collect();
+
+    let unexplained = combos
+        .iter()
+        .filter(|&&(ref c, failed)| failed && !explained.contains(&to_strings(c)))
+        .count();
+
+    (suggestions, unexplained)
+}
+
+/// Returns the smallest set of features found that every passing combination containing `feature`
+/// has at least one of, while no failing combination has any, if there is one.
+fn required_features(
+    feature: &str,
+    failing: &[&(BTreeSet<&str>, bool)],
+    passing: &[&(BTreeSet<&str>, bool)],
+) -> Option<Vec<String>> {
+    // Features that appear in a failing combination cannot prevent the failure.
+    let mut candidates: BTreeSet<&str> = passing
+        .iter()
+        .flat_map(|(c, _)| c.iter().copied())
+        .filter(|&f| f != feature && !failing.iter().any(|(c, _)| c.contains(f)))
+        .collect();
+
+    let mut uncovered: Vec<&BTreeSet<&str>> = passing.iter().map(|(c, _)| c).collect();
+    let mut any_of = Vec::new();
+
+    // Greedily pick the feature that covers the most passing combinations, until all are covered.
+    while !uncovered.is_empty() {
+        let best = candidates
+            .iter()
+            .copied()
+            .max_by_key(|&f| {
+                let covered = uncovered.iter().filter(|c| c.contains(f)).count();
+                // Prefer the first name on ties.
+                (covered, std::cmp::Reverse(f))
+            })
+            .filter(|&f| uncovered.iter().any(|c| c.contains(f)))?;
+
+        candidates.remove(best);
+        uncovered.retain(|c| !c.contains(best));
+        any_of.push(best.to_string());
+    }
+
+    any_of.sort_unstable();
+
+    Some(any_of)
+}
+
+fn to_strings(combo: &BTreeSet<&str>) -> Vec<String> {
+    combo.iter().map(|f| f.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Platform;
+
+    fn checks(combos: &[(&[&str], bool)]) -> Vec<CheckReport> {
+        combos
+            .iter()
+            .map(|&(features, passed)| CheckReport {
+                features: features.iter().map(|f| f.to_string()).collect(),
+                platform: Platform::default(),
+                outcome: if passed {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed {
+                        command: "cargo-check".to_string(),
+                    }
+                },
+                cached: false,
+                duration_secs: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn suggest_forbid_require_and_incompatible() {
+        let checks = checks(&[
+            (&[], true),
+            (&["a"], false),
+            (&["b"], true),
+            (&["c"], true),
+            (&["x"], true),
+            (&["y"], true),
+            (&["z"], false),
+            (&["a", "b"], true),
+            (&["a", "c"], true),
+            (&["a", "x"], false),
+            (&["b", "c"], true),
+            (&["x", "y"], false),
+        ]);
+
+        let (suggestions, unexplained) = suggest_rules(&checks);
+
+        let rules: Vec<&SuggestedRule> = suggestions.iter().map(|s| &s.rule).collect();
+
+        assert_eq!(
+            rules,
+            [
+                &SuggestedRule::Require {
+                    feature: "a".to_string(),
+                    any_of: vec!["b".to_string(), "c".to_string()],
+                },
+                &SuggestedRule::Forbid {
+                    feature: "z".to_string(),
+                },
+                &SuggestedRule::Incompatible {
+                    first: "x".to_string(),
+                    second: "y".to_string(),
+                },
+            ],
+        );
+
+        assert_eq!(suggestions[0].explains, [vec!["a"], vec!["a", "x"]]);
+        assert_eq!(unexplained, 0);
+
+        assert_eq!(
+            suggestions[0].to_string(),
+            "# Explains 2 failures: [\"a\"], [\"a\", \"x\"]\n[[rule]]\nwhen = \"a\"\nrequire = [\"b\", \"OR\", \"c\"]\n",
+        );
+    }
+
+    #[test]
+    fn unexplained_failures() {
+        // `a` fails only with `b` and `c` together, which is not a single feature or pair.
+        let checks = checks(&[
+            (&["a"], true),
+            (&["b"], true),
+            (&["c"], true),
+            (&["a", "b"], true),
+            (&["a", "c"], true),
+            (&["b", "c"], true),
+            (&["a", "b", "c"], false),
+        ]);
+
+        let (suggestions, unexplained) = suggest_rules(&checks);
+
+        assert!(suggestions.is_empty());
+        assert_eq!(unexplained, 1);
+    }
+
+    #[test]
+    fn forbid_instead_of_incompatible() {
+        // `c` never passes, even though it only fails along with `a`.
+        let checks = checks(&[(&["a"], true), (&["b"], true), (&["a", "c"], false)]);
+
+        let (suggestions, unexplained) = suggest_rules(&checks);
+
+        let rules: Vec<&SuggestedRule> = suggestions.iter().map(|s| &s.rule).collect();
+
+        assert_eq!(
+            rules,
+            [&SuggestedRule::Forbid {
+                feature: "c".to_string(),
+            }],
+        );
+        assert_eq!(unexplained, 0);
+    }
+}